@@ -1,45 +1,131 @@
 // The Abstract Syntax Tree (AST) for Leo
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+pub mod address;
+pub use address::{Address, Group};
+
+pub mod builder;
+
+pub mod field;
+pub use field::{Field, PrimeField, Scalar};
+
+pub mod integer;
+pub use integer::{Integer, IntegerType};
+
+pub mod json;
+
+pub mod symbol;
+pub use symbol::Symbol;
+
+pub mod visit;
+pub use visit::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub struct Program {
     // function main(a: u8) {
     //     let b = a + 1u8;
     // }
-    pub name: String,
+    pub name: Symbol,
     pub inputs: Vec<Input>,
     pub statements: Vec<Statement>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub struct Input {
-    pub name: String,
+    pub name: Symbol,
     pub input_type: Type,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub enum Type {
     U8,
+    Bool,
+    Field,
+    Scalar,
+    Group,
+    Address,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+//every statement keeps the span it was written at, which is left out of the JSON AST
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub enum Statement {
     // let a = 1u8;
     Assign {
-        variable: String,
+        variable: Symbol,
         expression: Expression,
+        #[serde(skip)]
+        span: SourceSpan,
+    },
+    // a = 2u8;
+    Reassign {
+        variable: Symbol,
+        expression: Expression,
+        #[serde(skip)]
+        span: SourceSpan,
+    },
+    // return a + 1u8;
+    Return {
+        expression: Expression,
+        #[serde(skip)]
+        span: SourceSpan,
+    },
+    // assert(a > 1u8);
+    Assert {
+        expression: Expression,
+        #[serde(skip)]
+        span: SourceSpan,
     },
     If {
         expression: Expression,
         statements_a: Vec<Statement>,
         statements_b: Vec<Statement>,
+        #[serde(skip)]
+        span: SourceSpan,
     },
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+// 1-based line and column positions, end inclusive of the last character
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+//where a statement was written in the Leo source, None for statements built in code or read
+//from JSON. Spans are not part of what a statement means, so any two compare equal and a pass
+//that only moves a statement leaves the program unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SourceSpan(pub Option<Span>);
+
+impl PartialEq for SourceSpan {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for SourceSpan {}
+
+impl Statement {
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            Statement::Assign { span, .. }
+            | Statement::Reassign { span, .. }
+            | Statement::Return { span, .. }
+            | Statement::Assert { span, .. }
+            | Statement::If { span, .. } => *span,
+        }
+    }
+}
+
+//PartialEq, Clone and Drop are implemented below and Serialize in `json`, so that long chains don't overflow the stack
+#[derive(Eq, Debug, Deserialize)]
 pub enum Expression {
     // 1u8 + 2u8
     Binary {
-        left: Value,
+        left: Box<Expression>,
         operator: Operator,
         right: Box<Expression>,
     },
@@ -47,19 +133,27 @@ pub enum Expression {
     Value(Box<Value>),
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub enum Value {
     // 1u8
-    Integer(u8),
+    Integer(Integer),
+    // 1field
+    Field(Field),
+    // 1scalar
+    Scalar(Scalar),
+    // 1group
+    Group(Group),
+    // aleo1...
+    Address(Address),
     // a
-    Identifier(String),
+    Identifier(Symbol),
     // true
     Boolean(bool),
     // (1u8 + a)
     Expression(Box<Expression>),
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub enum Operator {
     Add,
     Subtract,
@@ -70,15 +164,277 @@ pub enum Operator {
     Equal,
 }
 
-//trait to get the type of a variable for code generation
-//Value::integer(Type) enum in AST could be used instead
-trait TypeInfo {
-    fn type_of(&self) -> &'static str;
+//an expression taken apart, see `Expression::into_parts`
+pub enum Parts {
+    Binary(Expression, Operator, Expression),
+    Value(Value),
+}
+
+impl Program {
+    //how deeply blocks and parentheses nest, counting the function body as the first level
+    pub fn nesting_depth(&self) -> usize {
+        //walked with an explicit stack, this is used to reject trees too deep to recurse into
+        let mut deepest = 1;
+        let mut statements: Vec<(&Statement, usize)> =
+            self.statements.iter().map(|s| (s, 1)).collect();
+        let mut expressions: Vec<(&Expression, usize)> = Vec::new();
+        while let Some((statement, depth)) = statements.pop() {
+            match statement {
+                Statement::Assign { expression, .. }
+                | Statement::Reassign { expression, .. }
+                | Statement::Return { expression, .. }
+                | Statement::Assert { expression, .. } => expressions.push((expression, depth)),
+                Statement::If {
+                    expression,
+                    statements_a,
+                    statements_b,
+                    ..
+                } => {
+                    expressions.push((expression, depth));
+                    for statement in statements_a.iter().chain(statements_b) {
+                        statements.push((statement, depth + 1));
+                    }
+                }
+            }
+            deepest = deepest.max(depth);
+        }
+        while let Some((expression, depth)) = expressions.pop() {
+            deepest = deepest.max(depth);
+            for value in expression.values() {
+                if let Value::Expression(inner) = value {
+                    expressions.push((inner, depth + 1));
+                }
+            }
+        }
+        deepest
+    }
+}
+
+// Binary expressions follow the precedence of their operators and group from
+// the left, so `a - b * c - d` is `(a - (b * c)) - d`, and a long chain like
+// `1u8 + 1u8 + ... + 1u8` nests as deep as it is long. Dropping and comparing
+// trees uses an explicit stack, and everything else that recurses into a tree
+// goes through `grow_stack`. Expression implements Drop for this, which is why
+// it is taken apart with `into_parts` rather than by moving out of its fields
+// in a pattern.
+impl Expression {
+    //builds `first op1 v1 op2 v2 ...` from its values and operators, applying `*` and `/` before
+    //`+` and `-`, those before `<` and `>`, and those before `==`, each group from the left
+    pub fn infix(first: Value, rest: Vec<(Operator, Value)>) -> Expression {
+        //left sides still waiting for their right side, with the operator in between
+        let mut pending: Vec<(Expression, Operator)> = Vec::new();
+        let mut current = Expression::Value(Box::new(first));
+        for (operator, value) in rest {
+            while let Some((_, previous)) = pending.last() {
+                if previous.precedence() < operator.precedence() {
+                    break;
+                }
+                let (left, previous) = pending.pop().unwrap();
+                current = Expression::binary(left, previous, current);
+            }
+            pending.push((current, operator));
+            current = Expression::Value(Box::new(value));
+        }
+        while let Some((left, operator)) = pending.pop() {
+            current = Expression::binary(left, operator, current);
+        }
+        current
+    }
+
+    //`left operator right`, parenthesizing a side that would otherwise be read as part of
+    //another operation, e.g. `a + b` on the left of `*`
+    pub fn binary(left: Expression, operator: Operator, right: Expression) -> Expression {
+        let parenthesize = |expression: Expression| {
+            Expression::Value(Box::new(Value::Expression(Box::new(expression))))
+        };
+        let left = if left.groups_differently(&operator, true) {
+            parenthesize(left)
+        } else {
+            left
+        };
+        let right = if right.groups_differently(&operator, false) {
+            parenthesize(right)
+        } else {
+            right
+        };
+        Expression::Binary {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
+    }
+
+    //whether this side of `operator` needs parentheses to be read back as the same tree
+    fn groups_differently(&self, operator: &Operator, on_left: bool) -> bool {
+        match self {
+            Expression::Binary {
+                operator: inner, ..
+            } if on_left => inner.precedence() < operator.precedence(),
+            Expression::Binary {
+                operator: inner, ..
+            } => inner.precedence() <= operator.precedence(),
+            Expression::Value(_) => false,
+        }
+    }
+
+    //the value of an expression that is a single value
+    pub fn as_value(&self) -> Option<&Value> {
+        match self {
+            Expression::Value(value) => Some(value),
+            Expression::Binary { .. } => None,
+        }
+    }
+
+    //the values of the tree from left to right, not including those inside parentheses
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        let mut next = vec![self];
+        std::iter::from_fn(move || loop {
+            match next.pop()? {
+                Expression::Binary { left, right, .. } => {
+                    next.push(right);
+                    next.push(left);
+                }
+                Expression::Value(value) => return Some(&**value),
+            }
+        })
+    }
+
+    //splits the expression into its operation and operands, or its single value
+    pub fn into_parts(mut self) -> Parts {
+        match &mut self {
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => Parts::Binary(
+                take_expression(left),
+                operator.clone(),
+                take_expression(right),
+            ),
+            Expression::Value(value) => {
+                Parts::Value(std::mem::replace(&mut **value, Value::Boolean(false)))
+            }
+        }
+    }
+}
+
+impl Operator {
+    //how tightly the operator binds, higher precedences are applied first
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operator::Equal => 0,
+            Operator::GreaterThan | Operator::LessThan => 1,
+            Operator::Add | Operator::Subtract => 2,
+            Operator::Multiply | Operator::Divide => 3,
+        }
+    }
+}
+
+//runs a step of a recursive walk over a tree, moving to a fresh stack segment when the
+//current one runs low, since trees nest as deep as their longest chain or deepest parentheses
+pub fn grow_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(64 * 1024, 2 * 1024 * 1024, f)
+}
+
+//stack a single level of nesting may take, measured on pest and the statement walks of an
+//unoptimized build with room to spare
+const STACK_PER_LEVEL: usize = 16 * 1024;
+
+//runs `f` on a stack with room for `depth` levels of nested blocks or parentheses, for pest and
+//the walks over statements, which recurse once per level without growing the stack themselves
+pub fn grow_stack_for_depth<R>(depth: usize, f: impl FnOnce() -> R) -> R {
+    let size = depth.saturating_mul(STACK_PER_LEVEL);
+    stacker::maybe_grow(size + 64 * 1024, size + 2 * 1024 * 1024, f)
+}
+
+//moves an expression out of its box, leaving a cheap placeholder behind
+fn take_expression(expression: &mut Expression) -> Expression {
+    std::mem::replace(
+        expression,
+        Expression::Value(Box::new(Value::Boolean(false))),
+    )
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        //compare the trees node by node, including the contents of parentheses
+        let mut pairs = vec![(self, other)];
+        while let Some(pair) = pairs.pop() {
+            match pair {
+                (
+                    Expression::Binary {
+                        left: left_a,
+                        operator: operator_a,
+                        right: right_a,
+                    },
+                    Expression::Binary {
+                        left: left_b,
+                        operator: operator_b,
+                        right: right_b,
+                    },
+                ) => {
+                    if operator_a != operator_b {
+                        return false;
+                    }
+                    pairs.push((right_a, right_b));
+                    pairs.push((left_a, left_b));
+                }
+                (Expression::Value(a), Expression::Value(b)) => match (&**a, &**b) {
+                    (Value::Expression(a), Value::Expression(b)) => pairs.push((a, b)),
+                    (a, b) => {
+                        if a != b {
+                            return false;
+                        }
+                    }
+                },
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl Clone for Expression {
+    fn clone(&self) -> Self {
+        grow_stack(|| match self {
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => Expression::Binary {
+                left: left.clone(),
+                operator: operator.clone(),
+                right: right.clone(),
+            },
+            Expression::Value(value) => Expression::Value(value.clone()),
+        })
+    }
+}
+
+impl Drop for Expression {
+    fn drop(&mut self) {
+        //detach the nested expressions before this one is freed and drop them from a stack,
+        //each after its own nested expressions have been detached in turn
+        let mut nested = Vec::new();
+        detach_nested(self, &mut nested);
+        while let Some(mut expression) = nested.pop() {
+            detach_nested(&mut expression, &mut nested);
+        }
+    }
 }
 
-impl TypeInfo for u8 {
-    fn type_of(&self) -> &'static str {
-        "u8"
+//moves out the operands and the contents of parentheses, which are what trees nest through
+fn detach_nested(expression: &mut Expression, nested: &mut Vec<Expression>) {
+    match expression {
+        Expression::Binary { left, right, .. } => {
+            nested.push(take_expression(left));
+            nested.push(take_expression(right));
+        }
+        Expression::Value(value) => {
+            if let Value::Expression(inner) = &mut **value {
+                nested.push(take_expression(inner));
+            }
+        }
     }
 }
 
@@ -87,7 +443,7 @@ impl std::fmt::Display for Program {
         let inputs = self
             .inputs
             .iter()
-            .map(|input| format!("{:?}: {:?}", input.name, input.input_type))
+            .map(|input| format!("{}: {}", input.name, input.input_type))
             .collect::<Vec<String>>()
             .join(", ");
 
@@ -106,19 +462,73 @@ impl std::fmt::Display for Program {
     }
 }
 
+impl Type {
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::U8)
+    }
+}
+
+impl From<IntegerType> for Type {
+    fn from(integer_type: IntegerType) -> Self {
+        match integer_type {
+            IntegerType::U8 => Type::U8,
+        }
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Type::U8 => {
+                write!(f, "u8")
+            }
+            Type::Bool => {
+                write!(f, "bool")
+            }
+            Type::Field => {
+                write!(f, "field")
+            }
+            Type::Scalar => {
+                write!(f, "scalar")
+            }
+            Type::Group => {
+                write!(f, "group")
+            }
+            Type::Address => {
+                write!(f, "address")
+            }
+        }
+    }
+}
+
 impl std::fmt::Display for Statement {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Statement::Assign {
                 variable,
                 expression,
+                ..
             } => {
                 write!(f, "let {} = {};", variable, expression)
             }
+            Statement::Reassign {
+                variable,
+                expression,
+                ..
+            } => {
+                write!(f, "{} = {};", variable, expression)
+            }
+            Statement::Return { expression, .. } => {
+                write!(f, "return {};", expression)
+            }
+            Statement::Assert { expression, .. } => {
+                write!(f, "assert({});", expression)
+            }
             Statement::If {
                 expression,
                 statements_a,
                 statements_b,
+                ..
             } => {
                 let branch_a = statements_a
                     .iter()
@@ -144,18 +554,27 @@ impl std::fmt::Display for Statement {
 
 impl std::fmt::Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
+        grow_stack(|| match self {
             Expression::Binary {
                 left,
                 operator,
                 right,
             } => {
-                write!(f, "{} {} {}", left, operator, right)
-            }
-            Expression::Value(value) => {
-                write!(f, "{}", value)
+                //trees built by hand may hold sides that need parentheses to group the same way
+                if left.groups_differently(operator, true) {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                write!(f, " {} ", operator)?;
+                if right.groups_differently(operator, false) {
+                    write!(f, "({})", right)
+                } else {
+                    write!(f, "{}", right)
+                }
             }
-        }
+            Expression::Value(value) => write!(f, "{}", value),
+        })
     }
 }
 
@@ -191,7 +610,19 @@ impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Value::Integer(integer) => {
-                write!(f, "{}{}", integer, integer.type_of())
+                write!(f, "{}", integer)
+            }
+            Value::Field(field) => {
+                write!(f, "{}", field)
+            }
+            Value::Scalar(scalar) => {
+                write!(f, "{}", scalar)
+            }
+            Value::Group(group) => {
+                write!(f, "{}", group)
+            }
+            Value::Address(address) => {
+                write!(f, "{}", address)
             }
             Value::Identifier(identifier) => {
                 write!(f, "{}", identifier)
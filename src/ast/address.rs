@@ -0,0 +1,175 @@
+// Account addresses and group elements.
+//
+// An address literal like `aleo1qqq...` is the bech32m encoding of 32 bytes
+// under the human readable part `aleo`, and is checked when it is read so that
+// a mistyped address is reported instead of folded. Group literals like
+// `2group` name a multiple of the curve generator. Computing with them means
+// curve arithmetic, so they are only carried through the compiler unchanged.
+
+use num_bigint::{BigInt, Sign};
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Address(String);
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Unchecked")]
+pub struct Group {
+    #[serde(serialize_with = "super::integer::serialize_value")]
+    pub value: BigInt,
+}
+
+//a group element as read from JSON, before it is checked to be one a literal can write
+#[derive(Deserialize)]
+struct Unchecked {
+    #[serde(deserialize_with = "super::integer::deserialize_value")]
+    value: BigInt,
+}
+
+impl TryFrom<Unchecked> for Group {
+    type Error = String;
+
+    fn try_from(unchecked: Unchecked) -> Result<Self, Self::Error> {
+        if unchecked.value.sign() == Sign::Minus {
+            return Err(format!(
+                "value {} is out of range for group",
+                unchecked.value
+            ));
+        }
+        Ok(Group {
+            value: unchecked.value,
+        })
+    }
+}
+
+const PREFIX: &str = "aleo1";
+//the bech32 alphabet, each character stands for the five bit value of its position
+const CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+//52 characters for the 32 bytes of the address and 6 for the checksum
+const DATA_LENGTH: usize = 58;
+//what the checksum of a valid bech32m string leaves behind
+const BECH32M_CONSTANT: u32 = 0x2bc830a3;
+
+impl Address {
+    //the address written by a literal, or a description of what is wrong with it
+    pub fn parse(literal: &str) -> Result<Address, String> {
+        let invalid = |reason: &str| format!("invalid address `{}`: {}", literal, reason);
+        let data = literal
+            .strip_prefix(PREFIX)
+            .ok_or_else(|| invalid("addresses start with `aleo1`"))?;
+        if data.len() != DATA_LENGTH {
+            return Err(invalid(&format!(
+                "expected {} characters after `aleo1`, found {}",
+                DATA_LENGTH,
+                data.len()
+            )));
+        }
+        let mut values = expand_hrp("aleo");
+        for character in data.chars() {
+            match CHARSET.find(character) {
+                Some(value) => values.push(value as u8),
+                None => {
+                    return Err(invalid(&format!(
+                        "`{}` is not a bech32 character",
+                        character
+                    )))
+                }
+            }
+        }
+        if polymod(&values) != BECH32M_CONSTANT {
+            return Err(invalid("the checksum does not match"));
+        }
+        Ok(Address(literal.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+//the human readable part as it enters the checksum, high bits then low bits of every character
+fn expand_hrp(hrp: &str) -> Vec<u8> {
+    let mut values: Vec<u8> = hrp.bytes().map(|byte| byte >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|byte| byte & 31));
+    values
+}
+
+//the BCH checksum over five bit values defined by BIP 173
+fn polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut checksum: u32 = 1;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ u32::from(*value);
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+impl TryFrom<String> for Address {
+    type Error = String;
+
+    fn try_from(literal: String) -> Result<Self, Self::Error> {
+        Address::parse(&literal)
+    }
+}
+
+impl From<Address> for String {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+impl From<u64> for Group {
+    fn from(value: u64) -> Self {
+        Group {
+            value: BigInt::from(value),
+        }
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::fmt::Display for Group {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}group", self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_addresses() {
+        for literal in [
+            "aleo1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzc",
+            "aleo1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0sctexzp",
+        ] {
+            assert_eq!(Address::parse(literal).unwrap().as_str(), literal);
+        }
+    }
+
+    #[test]
+    fn test_invalid_addresses() {
+        assert_eq!(
+            Address::parse("aleo1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzq"),
+            Err(String::from("invalid address `aleo1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzq`: the checksum does not match"))
+        );
+        assert!(Address::parse("aleo1qqqq").is_err());
+        assert!(
+            Address::parse("aleo1bqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzc")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,454 @@
+// Helpers for constructing ASTs in code, mainly for tests and tooling.
+//
+// The functions below take care of the boxing, e.g.
+// binary(ident("a"), Operator::Add, int(1)) is `a + 1u8`. The `leo!` macro
+// goes one step further and accepts Leo syntax directly, turning it into the
+// same constructors at compile time:
+//
+// leo!(a + (1u8 * b))
+// leo!(function main(a: u8) {
+//     let b = a + 1u8;
+//     if b > 2u8 { let c = true; } else { let c = false; }
+// })
+//
+// Like the parser, the macro applies operators by precedence and groups them
+// from the left, while `binary` builds exactly the tree it is given. Rust
+// reads `2field`, `3scalar` and `0group` as literals with an unknown suffix,
+// which the macro hands on as text, so every Leo literal can be written as in
+// Leo, and a name starting with `aleo1` is an address as it is for the parser.
+
+use super::*;
+use num_bigint::BigInt;
+
+// 1u8
+pub fn int(value: u8) -> Value {
+    Value::Integer(Integer::from(value))
+}
+
+// 1field
+pub fn field(value: u64) -> Value {
+    Value::Field(Field::from(value))
+}
+
+// 1scalar
+pub fn scalar(value: u64) -> Value {
+    Value::Scalar(Scalar::from(value))
+}
+
+// 1group
+pub fn group(value: u64) -> Value {
+    Value::Group(Group::from(value))
+}
+
+// aleo1..., panics if the address is not valid
+pub fn address(literal: &str) -> Value {
+    Value::Address(Address::parse(literal).unwrap())
+}
+
+// 1u8, 2field, 3scalar, 0group or true, from the text of a literal, panics if it is not one
+pub fn literal(literal: &str) -> Value {
+    let suffix_start = literal
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(literal.len());
+    let (digits, suffix) = literal.split_at(suffix_start);
+    if digits.is_empty() {
+        return boolean(suffix.parse().expect("not a Leo literal"));
+    }
+    let value: BigInt = digits.parse().expect("not a Leo literal");
+    match suffix {
+        "field" => Value::Field(Field::new(value).expect("field out of range")),
+        "scalar" => Value::Scalar(Scalar::new(value).expect("scalar out of range")),
+        "group" => Value::Group(Group { value }),
+        suffix => {
+            let integer_type = IntegerType::from_suffix(suffix).expect("not a Leo literal");
+            Value::Integer(Integer::new(integer_type, value).expect("integer out of range"))
+        }
+    }
+}
+
+// true
+pub fn boolean(value: bool) -> Value {
+    Value::Boolean(value)
+}
+
+// a
+pub fn ident(name: &str) -> Value {
+    Value::Identifier(Symbol::new(name))
+}
+
+// a, or aleo1... for a name the parser would read as an address
+pub fn name(name: &str) -> Value {
+    if name.starts_with("aleo1") {
+        address(name)
+    } else {
+        ident(name)
+    }
+}
+
+// (1u8 + a)
+pub fn paren(expression: impl Into<Expression>) -> Value {
+    Value::Expression(Box::new(expression.into()))
+}
+
+// 1u8 + a
+pub fn binary(
+    left: impl Into<Expression>,
+    operator: Operator,
+    right: impl Into<Expression>,
+) -> Expression {
+    Expression::binary(left.into(), operator, right.into())
+}
+
+// let a = 1u8;
+pub fn assign(variable: &str, expression: impl Into<Expression>) -> Statement {
+    Statement::Assign {
+        variable: Symbol::new(variable),
+        expression: expression.into(),
+        span: SourceSpan::default(),
+    }
+}
+
+// a = 1u8;
+pub fn reassign(variable: &str, expression: impl Into<Expression>) -> Statement {
+    Statement::Reassign {
+        variable: Symbol::new(variable),
+        expression: expression.into(),
+        span: SourceSpan::default(),
+    }
+}
+
+// return a;
+pub fn return_value(expression: impl Into<Expression>) -> Statement {
+    Statement::Return {
+        expression: expression.into(),
+        span: SourceSpan::default(),
+    }
+}
+
+// assert(a);
+pub fn assert(condition: impl Into<Expression>) -> Statement {
+    Statement::Assert {
+        expression: condition.into(),
+        span: SourceSpan::default(),
+    }
+}
+
+// if a { ... } else { ... }
+pub fn if_else(
+    condition: impl Into<Expression>,
+    statements_a: Vec<Statement>,
+    statements_b: Vec<Statement>,
+) -> Statement {
+    Statement::If {
+        expression: condition.into(),
+        statements_a,
+        statements_b,
+        span: SourceSpan::default(),
+    }
+}
+
+impl From<Value> for Expression {
+    fn from(value: Value) -> Self {
+        Expression::Value(Box::new(value))
+    }
+}
+
+impl From<u8> for Value {
+    fn from(value: u8) -> Self {
+        Value::Integer(Integer::from(value))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+// builds a Program one input and statement at a time
+pub struct ProgramBuilder {
+    program: Program,
+}
+
+impl ProgramBuilder {
+    pub fn new(name: &str) -> Self {
+        ProgramBuilder {
+            program: Program {
+                name: Symbol::new(name),
+                inputs: Vec::new(),
+                statements: Vec::new(),
+            },
+        }
+    }
+
+    pub fn input(mut self, name: &str, input_type: Type) -> Self {
+        self.program.inputs.push(Input {
+            name: Symbol::new(name),
+            input_type,
+        });
+        self
+    }
+
+    pub fn statement(mut self, statement: Statement) -> Self {
+        self.program.statements.push(statement);
+        self
+    }
+
+    pub fn build(self) -> Program {
+        self.program
+    }
+}
+
+// Leo syntax to AST, see the top of this file. Expressions are handled by
+// __leo_expression, statements by __leo_statements.
+#[macro_export]
+macro_rules! leo {
+    (function $name:ident ( $($input:ident : $input_type:ident),* $(,)? ) { $($body:tt)* }) => {
+        $crate::ast::Program {
+            name: $crate::ast::Symbol::new(stringify!($name)),
+            inputs: vec![$($crate::ast::Input {
+                name: $crate::ast::Symbol::new(stringify!($input)),
+                input_type: $crate::__leo_type!($input_type),
+            }),*],
+            statements: $crate::__leo_statements!([] $($body)*),
+        }
+    };
+    ($($expression:tt)+) => {
+        $crate::__leo_expression!($($expression)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __leo_type {
+    (u8) => {
+        $crate::ast::Type::U8
+    };
+    (bool) => {
+        $crate::ast::Type::Bool
+    };
+    (field) => {
+        $crate::ast::Type::Field
+    };
+    (scalar) => {
+        $crate::ast::Type::Scalar
+    };
+    (group) => {
+        $crate::ast::Type::Group
+    };
+    (address) => {
+        $crate::ast::Type::Address
+    };
+}
+
+// munches statements one token at a time, collecting the finished ones in the leading brackets
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __leo_statements {
+    ([$($done:expr),*]) => {
+        vec![$($done),*]
+    };
+    ([$($done:expr),*] let $variable:ident = $($rest:tt)*) => {
+        $crate::__leo_statements!(@let [$($done),*] $variable [] $($rest)*)
+    };
+    ([$($done:expr),*] if $($rest:tt)*) => {
+        $crate::__leo_statements!(@if [$($done),*] [] $($rest)*)
+    };
+    ([$($done:expr),*] return $($rest:tt)*) => {
+        $crate::__leo_statements!(@return [$($done),*] [] $($rest)*)
+    };
+    ([$($done:expr),*] assert ( $($condition:tt)+ ) ; $($rest:tt)*) => {
+        $crate::__leo_statements!([$($done,)* $crate::ast::builder::assert(
+            $crate::__leo_expression!($($condition)+),
+        )] $($rest)*)
+    };
+    ([$($done:expr),*] $variable:ident = $($rest:tt)*) => {
+        $crate::__leo_statements!(@reassign [$($done),*] $variable [] $($rest)*)
+    };
+    (@let [$($done:expr),*] $variable:ident [$($expression:tt)+] ; $($rest:tt)*) => {
+        $crate::__leo_statements!([$($done,)* $crate::ast::builder::assign(
+            stringify!($variable),
+            $crate::__leo_expression!($($expression)+),
+        )] $($rest)*)
+    };
+    (@let [$($done:expr),*] $variable:ident [$($expression:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__leo_statements!(@let [$($done),*] $variable [$($expression)* $next] $($rest)*)
+    };
+    (@reassign [$($done:expr),*] $variable:ident [$($expression:tt)+] ; $($rest:tt)*) => {
+        $crate::__leo_statements!([$($done,)* $crate::ast::builder::reassign(
+            stringify!($variable),
+            $crate::__leo_expression!($($expression)+),
+        )] $($rest)*)
+    };
+    (@reassign [$($done:expr),*] $variable:ident [$($expression:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__leo_statements!(@reassign [$($done),*] $variable [$($expression)* $next] $($rest)*)
+    };
+    (@return [$($done:expr),*] [$($expression:tt)+] ; $($rest:tt)*) => {
+        $crate::__leo_statements!([$($done,)* $crate::ast::builder::return_value(
+            $crate::__leo_expression!($($expression)+),
+        )] $($rest)*)
+    };
+    (@return [$($done:expr),*] [$($expression:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__leo_statements!(@return [$($done),*] [$($expression)* $next] $($rest)*)
+    };
+    (@if [$($done:expr),*] [$($condition:tt)+] { $($a:tt)* } else { $($b:tt)* } $($rest:tt)*) => {
+        $crate::__leo_statements!([$($done,)* $crate::ast::builder::if_else(
+            $crate::__leo_expression!($($condition)+),
+            $crate::__leo_statements!([] $($a)*),
+            $crate::__leo_statements!([] $($b)*),
+        )] $($rest)*)
+    };
+    (@if [$($done:expr),*] [$($condition:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__leo_statements!(@if [$($done),*] [$($condition)* $next] $($rest)*)
+    };
+}
+
+// collects the values and operators and leaves grouping them to Expression::infix
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __leo_expression {
+    ($first:tt $($operator:tt $value:tt)*) => {
+        $crate::ast::Expression::infix(
+            $crate::__leo_value!($first),
+            vec![$(($crate::__leo_operator!($operator), $crate::__leo_value!($value))),*],
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __leo_operator {
+    (+) => {
+        $crate::ast::Operator::Add
+    };
+    (-) => {
+        $crate::ast::Operator::Subtract
+    };
+    (*) => {
+        $crate::ast::Operator::Multiply
+    };
+    (/) => {
+        $crate::ast::Operator::Divide
+    };
+    (>) => {
+        $crate::ast::Operator::GreaterThan
+    };
+    (<) => {
+        $crate::ast::Operator::LessThan
+    };
+    (==) => {
+        $crate::ast::Operator::Equal
+    };
+}
+
+// literals are read from their text with their Leo suffix, so an unsuffixed integer panics
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __leo_value {
+    (( $($expression:tt)+ )) => {
+        $crate::ast::builder::paren($crate::__leo_expression!($($expression)+))
+    };
+    ($literal:literal) => {
+        $crate::ast::builder::literal(stringify!($literal))
+    };
+    ($name:ident) => {
+        $crate::ast::builder::name(stringify!($name))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_builder_matches_parser() {
+        let built = ProgramBuilder::new("main")
+            .input("a", Type::U8)
+            .statement(assign("b", binary(ident("a"), Operator::Add, int(1))))
+            .statement(if_else(
+                binary(ident("b"), Operator::GreaterThan, int(2)),
+                vec![assign("c", boolean(true))],
+                vec![assign("c", boolean(false))],
+            ))
+            .build();
+        let parsed = parse("function main(a: u8) {\n    let b = a + 1u8;\n    if b > 2u8 {\n        let c = true;\n    } else {\n        let c = false;\n    }\n}").unwrap();
+        assert_eq!(built, parsed);
+    }
+
+    #[test]
+    fn test_macro_expression() {
+        assert_eq!(
+            leo!((1u8 + a) * b == true),
+            binary(
+                binary(
+                    paren(binary(int(1), Operator::Add, ident("a"))),
+                    Operator::Multiply,
+                    ident("b"),
+                ),
+                Operator::Equal,
+                boolean(true),
+            )
+        );
+        assert_eq!(
+            leo!(a - b * c - d),
+            binary(
+                binary(
+                    ident("a"),
+                    Operator::Subtract,
+                    binary(ident("b"), Operator::Multiply, ident("c")),
+                ),
+                Operator::Subtract,
+                ident("d"),
+            )
+        );
+    }
+
+    #[test]
+    fn test_macro_literals() {
+        assert_eq!(
+            leo!(
+                2field * 3scalar + 0group
+                    == aleo1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzc
+            ),
+            binary(
+                binary(
+                    binary(field(2), Operator::Multiply, scalar(3)),
+                    Operator::Add,
+                    group(0),
+                ),
+                Operator::Equal,
+                address("aleo1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzc"),
+            )
+        );
+        assert_eq!(
+            leo!(255u8 == false),
+            binary(int(255), Operator::Equal, boolean(false))
+        );
+    }
+
+    #[test]
+    fn test_macro_program() {
+        let built = leo!(function main(a: u8, flag: bool) {
+            let b = a + 1u8;
+            if flag {
+                let c = b;
+            } else {
+                let c = 2u8;
+            }
+            let d = true;
+        });
+        let expected = ProgramBuilder::new("main")
+            .input("a", Type::U8)
+            .input("flag", Type::Bool)
+            .statement(assign("b", binary(ident("a"), Operator::Add, int(1))))
+            .statement(if_else(
+                ident("flag"),
+                vec![assign("c", ident("b"))],
+                vec![assign("c", int(2))],
+            ))
+            .statement(assign("d", boolean(true)))
+            .build();
+        assert_eq!(built, expected);
+    }
+}
@@ -0,0 +1,202 @@
+// Elements of the prime fields Aleo programs compute over.
+//
+// `Field` is an element of the base field and `Scalar` an element of the
+// scalar field, the order of the group generator. Both hold their value as a
+// `BigInt` reduced below their modulus and share the arithmetic in
+// `PrimeField`, which wraps around the modulus instead of overflowing.
+// Division multiplies by the modular inverse of the divisor, which exists for
+// every element except zero since both moduli are prime.
+
+use num_bigint::BigInt;
+use num_traits::Zero;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+//the order of the base field of the curve Aleo programs compute over
+pub const FIELD_MODULUS: &str =
+    "8444461749428370424248824938781546531375899335154063827935233455917409239041";
+
+//the order of the subgroup generated by the curve generator
+pub const SCALAR_MODULUS: &str =
+    "2111115437357092606062206234695386632838870926408408195193685246394721360383";
+
+pub trait PrimeField: Sized {
+    //the literal suffix and type name, e.g. `field`
+    const NAME: &'static str;
+
+    fn modulus() -> &'static BigInt;
+
+    fn value(&self) -> &BigInt;
+
+    //wraps a value that is already below the modulus
+    fn from_reduced(value: BigInt) -> Self;
+
+    //the element with the given value, or None if the value is negative or not below the modulus
+    fn new(value: BigInt) -> Option<Self> {
+        if value < BigInt::zero() || &value >= Self::modulus() {
+            return None;
+        }
+        Some(Self::from_reduced(value))
+    }
+
+    //the element congruent to any value
+    fn reduce(value: BigInt) -> Self {
+        let modulus = Self::modulus();
+        Self::from_reduced(((value % modulus) + modulus) % modulus)
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Self::reduce(self.value() + other.value())
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        Self::reduce(self.value() - other.value())
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        Self::reduce(self.value() * other.value())
+    }
+
+    //multiplies by the inverse of the divisor, None when dividing by zero
+    fn div(&self, other: &Self) -> Option<Self> {
+        Some(self.mul(&other.inverse()?))
+    }
+
+    //the inverse by Fermat's little theorem, `x^(p - 2)`, None for zero
+    fn inverse(&self) -> Option<Self> {
+        if self.value().is_zero() {
+            return None;
+        }
+        let modulus = Self::modulus();
+        Some(Self::from_reduced(
+            self.value().modpow(&(modulus - 2), modulus),
+        ))
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Unchecked")]
+pub struct Field {
+    #[serde(serialize_with = "super::integer::serialize_value")]
+    pub value: BigInt,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Unchecked")]
+pub struct Scalar {
+    #[serde(serialize_with = "super::integer::serialize_value")]
+    pub value: BigInt,
+}
+
+impl PrimeField for Field {
+    const NAME: &'static str = "field";
+
+    fn modulus() -> &'static BigInt {
+        static MODULUS: OnceLock<BigInt> = OnceLock::new();
+        MODULUS.get_or_init(|| FIELD_MODULUS.parse().unwrap())
+    }
+
+    fn value(&self) -> &BigInt {
+        &self.value
+    }
+
+    fn from_reduced(value: BigInt) -> Self {
+        Field { value }
+    }
+}
+
+impl PrimeField for Scalar {
+    const NAME: &'static str = "scalar";
+
+    fn modulus() -> &'static BigInt {
+        static MODULUS: OnceLock<BigInt> = OnceLock::new();
+        MODULUS.get_or_init(|| SCALAR_MODULUS.parse().unwrap())
+    }
+
+    fn value(&self) -> &BigInt {
+        &self.value
+    }
+
+    fn from_reduced(value: BigInt) -> Self {
+        Scalar { value }
+    }
+}
+
+//an element as read from JSON, before its range has been checked
+#[derive(Deserialize)]
+struct Unchecked {
+    #[serde(deserialize_with = "super::integer::deserialize_value")]
+    value: BigInt,
+}
+
+impl Unchecked {
+    fn check<T: PrimeField>(self) -> Result<T, String> {
+        let message = format!("value {} is out of range for {}", self.value, T::NAME);
+        T::new(self.value).ok_or(message)
+    }
+}
+
+impl TryFrom<Unchecked> for Field {
+    type Error = String;
+
+    fn try_from(unchecked: Unchecked) -> Result<Self, Self::Error> {
+        unchecked.check()
+    }
+}
+
+impl TryFrom<Unchecked> for Scalar {
+    type Error = String;
+
+    fn try_from(unchecked: Unchecked) -> Result<Self, Self::Error> {
+        unchecked.check()
+    }
+}
+
+impl From<u64> for Field {
+    fn from(value: u64) -> Self {
+        Field::reduce(BigInt::from(value))
+    }
+}
+
+impl From<u64> for Scalar {
+    fn from(value: u64) -> Self {
+        Scalar::reduce(BigInt::from(value))
+    }
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, Field::NAME)
+    }
+}
+
+impl std::fmt::Display for Scalar {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, Scalar::NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wraps_around_the_modulus() {
+        let largest = Field::reduce(BigInt::from(-1));
+        assert_eq!(&largest.value, &(Field::modulus() - 1));
+        assert_eq!(largest.add(&Field::from(2)), Field::from(1));
+        assert_eq!(Field::from(1).sub(&Field::from(2)), largest);
+        assert!(Field::new(Field::modulus().clone()).is_none());
+        assert!(Scalar::new(Field::modulus() - 1).is_none());
+    }
+
+    #[test]
+    fn test_division() {
+        let third = Field::from(1).div(&Field::from(3)).unwrap();
+        assert_eq!(third.mul(&Field::from(3)), Field::from(1));
+        assert_eq!(Field::from(12).div(&Field::from(4)), Some(Field::from(3)));
+        assert_eq!(Field::from(1).div(&Field::from(0)), None);
+        let third = Scalar::from(1).div(&Scalar::from(3)).unwrap();
+        assert_eq!(third.mul(&Scalar::from(3)), Scalar::from(1));
+    }
+}
@@ -0,0 +1,152 @@
+// Typed integer literals.
+//
+// An `Integer` pairs its Leo type with the value as an arbitrary precision
+// `BigInt`, so every integer type shares one representation and the range of a
+// type is described by its width and signedness rather than by a Rust
+// primitive. Arithmetic is done exactly and the result is then checked
+// against the range of the type.
+
+use num_bigint::BigInt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum IntegerType {
+    U8,
+}
+
+impl IntegerType {
+    pub fn bits(self) -> u32 {
+        match self {
+            IntegerType::U8 => 8,
+        }
+    }
+
+    pub fn is_signed(self) -> bool {
+        match self {
+            IntegerType::U8 => false,
+        }
+    }
+
+    pub fn min(self) -> BigInt {
+        if self.is_signed() {
+            -(BigInt::from(1) << (self.bits() - 1))
+        } else {
+            BigInt::from(0)
+        }
+    }
+
+    pub fn max(self) -> BigInt {
+        let magnitude = if self.is_signed() {
+            self.bits() - 1
+        } else {
+            self.bits()
+        };
+        (BigInt::from(1) << magnitude) - 1
+    }
+
+    //the type written as a literal suffix, e.g. `u8` in `1u8`
+    pub fn from_suffix(suffix: &str) -> Option<IntegerType> {
+        match suffix {
+            "u8" => Some(IntegerType::U8),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for IntegerType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            IntegerType::U8 => write!(f, "u8"),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedInteger")]
+pub struct Integer {
+    pub integer_type: IntegerType,
+    #[serde(serialize_with = "serialize_value")]
+    pub value: BigInt,
+}
+
+//an integer as read from JSON, before its range has been checked
+#[derive(Deserialize)]
+struct UncheckedInteger {
+    integer_type: IntegerType,
+    #[serde(deserialize_with = "deserialize_value")]
+    value: BigInt,
+}
+
+impl TryFrom<UncheckedInteger> for Integer {
+    type Error = String;
+
+    fn try_from(integer: UncheckedInteger) -> Result<Self, Self::Error> {
+        let message = format!(
+            "value {} is out of range for {}",
+            integer.value, integer.integer_type
+        );
+        Integer::new(integer.integer_type, integer.value).ok_or(message)
+    }
+}
+
+impl Integer {
+    //an integer of the given type, or None if the value is out of its range
+    pub fn new(integer_type: IntegerType, value: BigInt) -> Option<Integer> {
+        if value < integer_type.min() || value > integer_type.max() {
+            return None;
+        }
+        Some(Integer {
+            integer_type,
+            value,
+        })
+    }
+}
+
+impl From<u8> for Integer {
+    fn from(value: u8) -> Self {
+        Integer {
+            integer_type: IntegerType::U8,
+            value: BigInt::from(value),
+        }
+    }
+}
+
+impl std::fmt::Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, self.integer_type)
+    }
+}
+
+//values are written as decimal strings, which JSON numbers cannot hold for the wider types
+pub(super) fn serialize_value<S: Serializer>(
+    value: &BigInt,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+pub(super) fn deserialize_value<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BigInt, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range() {
+        assert_eq!(IntegerType::U8.min(), BigInt::from(0));
+        assert_eq!(IntegerType::U8.max(), BigInt::from(255));
+        assert!(Integer::new(IntegerType::U8, BigInt::from(255)).is_some());
+        assert!(Integer::new(IntegerType::U8, BigInt::from(256)).is_none());
+        assert!(Integer::new(IntegerType::U8, BigInt::from(-1)).is_none());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Integer::from(42).to_string(), "42u8");
+    }
+}
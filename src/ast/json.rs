@@ -0,0 +1,136 @@
+// JSON import and export of the AST.
+//
+// A document wraps the program together with the schema version, e.g.
+// {"version": 4, "program": {"name": "main", "inputs": [], "statements": [...]}}
+// The version is bumped whenever the shape of the AST types changes, and
+// documents with any other version are rejected instead of misread.
+//
+// The JSON nests as deep as the longest expression chain. Reading goes through
+// serde_stacker and writing through the Serialize impl for Expression below,
+// both of which move to a fresh stack segment when the current one runs low
+// instead of overflowing.
+
+use super::{grow_stack, Expression, Program};
+use serde::ser::SerializeStructVariant;
+use serde::{Deserialize, Serialize, Serializer};
+
+pub const SCHEMA_VERSION: u32 = 4;
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u32,
+    program: &'a Program,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    version: u32,
+    program: Program,
+}
+
+//serializes a program into a versioned JSON document
+pub fn to_json(program: &Program) -> String {
+    let document = DocumentRef {
+        version: SCHEMA_VERSION,
+        program,
+    };
+    let mut json = Vec::new();
+    document
+        .serialize(&mut serde_json::Serializer::new(&mut json))
+        .expect("the AST is always serializable");
+    String::from_utf8(json).expect("serde_json writes UTF-8")
+}
+
+//reads a program back from a versioned JSON document, describing the problem on failure
+pub fn from_json(source: &str) -> Result<Program, String> {
+    let mut deserializer = serde_json::Deserializer::from_str(source);
+    deserializer.disable_recursion_limit();
+    let document = Document::deserialize(serde_stacker::Deserializer::new(&mut deserializer))
+        .and_then(|document| deserializer.end().map(|_| document))
+        .map_err(|e| e.to_string())?;
+    if document.version != SCHEMA_VERSION {
+        return Err(format!(
+            "unsupported AST schema version {}, expected {}",
+            document.version, SCHEMA_VERSION
+        ));
+    }
+    Ok(document.program)
+}
+
+//the same externally tagged layout serde derives, e.g. {"Value": {"Integer": 1}}
+impl Serialize for Expression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        grow_stack(|| match self {
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let mut variant =
+                    serializer.serialize_struct_variant("Expression", 0, "Binary", 3)?;
+                variant.serialize_field("left", left)?;
+                variant.serialize_field("operator", operator)?;
+                variant.serialize_field("right", right)?;
+                variant.end()
+            }
+            Expression::Value(value) => {
+                serializer.serialize_newtype_variant("Expression", 1, "Value", value)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_round_trip() {
+        let program = parse("function main(a: u8, flag: bool) {\n    let b = a + 1u8;\n    if flag {\n        let c = b;\n    } else {\n        let c = true;\n    }\n}").unwrap();
+        assert_eq!(from_json(&to_json(&program)), Ok(program));
+    }
+
+    #[test]
+    fn test_round_trip_literals() {
+        let program = parse("function main(g: group) {\n    let a = 3field * 2scalar;\n    let b = g + 0group;\n    let c = aleo1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzc;\n}").unwrap();
+        assert_eq!(from_json(&to_json(&program)), Ok(program));
+    }
+
+    #[test]
+    fn test_round_trip_long_chain() {
+        let chain = vec!["1u8"; 20_000].join(" + ");
+        let program = parse(&format!("function main() {{\n    let a = {};\n}}", chain)).unwrap();
+        assert_eq!(from_json(&to_json(&program)), Ok(program));
+    }
+
+    #[test]
+    fn test_rejects_integers_out_of_range() {
+        let source = r#"{"version": 4, "program": {"name": "main", "inputs": [], "statements": [
+            {"Assign": {"variable": "a", "expression": {"Value": {"Integer": {"integer_type": "U8", "value": "256"}}}}}
+        ]}}"#;
+        assert!(from_json(source)
+            .unwrap_err()
+            .starts_with("value 256 is out of range for u8"));
+    }
+
+    #[test]
+    fn test_rejects_negative_groups() {
+        let source = r#"{"version": 4, "program": {"name": "main", "inputs": [], "statements": [
+            {"Assign": {"variable": "a", "expression": {"Value": {"Group": {"value": "-5"}}}}}
+        ]}}"#;
+        assert!(from_json(source)
+            .unwrap_err()
+            .starts_with("value -5 is out of range for group"));
+    }
+
+    #[test]
+    fn test_rejects_other_versions() {
+        let source =
+            r#"{"version": 1, "program": {"name": "main", "inputs": [], "statements": []}}"#;
+        assert_eq!(
+            from_json(source),
+            Err(String::from("unsupported AST schema version 1, expected 4"))
+        );
+    }
+}
@@ -0,0 +1,92 @@
+// Identifiers.
+//
+// Every name in the AST is a `Symbol`, a reference counted string. Cloning a
+// symbol only bumps the count, so passes can copy names into their tables
+// without allocating, and a name's string is freed along with the last tree
+// or table that holds it. Symbols are not interned: comparing and hashing
+// them looks at the whole string rather than a single integer, and every
+// occurrence in the source gets its own allocation. The upside is that there
+// is no global table to lock or grow for the lifetime of the process, and
+// compilations on different threads share nothing.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::Arc;
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    //returns a symbol holding its own copy of the name
+    pub fn new(name: &str) -> Symbol {
+        Symbol(Arc::from(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::new(name)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl std::fmt::Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+//names are written as plain strings
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(Symbol::new(&name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols_compare_by_name() {
+        let a = Symbol::new("name");
+        assert_eq!(a, Symbol::new("name"));
+        assert_ne!(a, Symbol::new("other_name"));
+        assert_eq!(a, "name");
+        assert_eq!(a.to_string(), "name");
+    }
+
+    #[test]
+    fn test_clones_share_the_name() {
+        let a = Symbol::new("name");
+        assert!(Arc::ptr_eq(&a.0, &a.clone().0));
+        assert!(!Arc::ptr_eq(&a.0, &Symbol::new("name").0));
+    }
+}
@@ -0,0 +1,303 @@
+// Traits for writing passes over the AST.
+//
+// `Visitor` walks a borrowed tree and is meant for analyses, `Reconstructor`
+// takes the tree by value and rebuilds it, which is how rewriting passes like
+// the constant folder are written. Every method has a default implementation
+// that recurses into the children of the node, so a pass only overrides the
+// nodes it cares about and calls the matching `walk_*` or `rebuild_*`
+// function to keep recursing.
+//
+// `visit_expression` and `reconstruct_expression` are called for every
+// operand of a binary expression in turn, e.g. for `a + b * c`, then `a` and
+// `b * c`. Long chains nest as deep as they are long, so the walks go through
+// `grow_stack` and cannot overflow the stack.
+
+use super::*;
+
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_input(&mut self, _input: &Input) {}
+
+    //called for the statements of each if branch, which form their own scope
+    fn visit_branch(&mut self, statements: &[Statement]) {
+        walk_statements(self, statements)
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement)
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression)
+    }
+
+    fn visit_value(&mut self, value: &Value) {
+        walk_value(self, value)
+    }
+
+    fn visit_operator(&mut self, _operator: &Operator) {}
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for input in &program.inputs {
+        visitor.visit_input(input);
+    }
+    walk_statements(visitor, &program.statements);
+}
+
+pub fn walk_statements<V: Visitor + ?Sized>(visitor: &mut V, statements: &[Statement]) {
+    for statement in statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::Assign { expression, .. }
+        | Statement::Reassign { expression, .. }
+        | Statement::Return { expression, .. }
+        | Statement::Assert { expression, .. } => visitor.visit_expression(expression),
+        Statement::If {
+            expression,
+            statements_a,
+            statements_b,
+            ..
+        } => {
+            visitor.visit_expression(expression);
+            visitor.visit_branch(statements_a);
+            visitor.visit_branch(statements_b);
+        }
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    grow_stack(|| match expression {
+        Expression::Binary {
+            left,
+            operator,
+            right,
+        } => {
+            visitor.visit_expression(left);
+            visitor.visit_operator(operator);
+            visitor.visit_expression(right);
+        }
+        Expression::Value(value) => visitor.visit_value(value),
+    })
+}
+
+pub fn walk_value<V: Visitor + ?Sized>(visitor: &mut V, value: &Value) {
+    if let Value::Expression(expression) = value {
+        visitor.visit_expression(expression);
+    }
+}
+
+pub trait Reconstructor {
+    //error that aborts the rewrite, use `std::convert::Infallible` for passes that cannot fail
+    type Error;
+
+    fn reconstruct_program(&mut self, program: Program) -> Result<Program, Self::Error> {
+        rebuild_program(self, program)
+    }
+
+    fn reconstruct_statements(
+        &mut self,
+        statements: Vec<Statement>,
+    ) -> Result<Vec<Statement>, Self::Error> {
+        rebuild_statements(self, statements)
+    }
+
+    //called for the statements of each if branch, which form their own scope
+    fn reconstruct_branch(
+        &mut self,
+        statements: Vec<Statement>,
+    ) -> Result<Vec<Statement>, Self::Error> {
+        self.reconstruct_statements(statements)
+    }
+
+    //a statement may be rewritten into any number of statements, including none
+    fn reconstruct_statement(
+        &mut self,
+        statement: Statement,
+    ) -> Result<Vec<Statement>, Self::Error> {
+        rebuild_statement(self, statement)
+    }
+
+    fn reconstruct_expression(
+        &mut self,
+        expression: Expression,
+    ) -> Result<Expression, Self::Error> {
+        rebuild_expression(self, expression)
+    }
+
+    fn reconstruct_value(&mut self, value: Value) -> Result<Value, Self::Error> {
+        rebuild_value(self, value)
+    }
+}
+
+pub fn rebuild_program<R: Reconstructor + ?Sized>(
+    reconstructor: &mut R,
+    program: Program,
+) -> Result<Program, R::Error> {
+    Ok(Program {
+        name: program.name,
+        inputs: program.inputs,
+        statements: reconstructor.reconstruct_statements(program.statements)?,
+    })
+}
+
+pub fn rebuild_statements<R: Reconstructor + ?Sized>(
+    reconstructor: &mut R,
+    statements: Vec<Statement>,
+) -> Result<Vec<Statement>, R::Error> {
+    let mut new_statements = Vec::with_capacity(statements.len());
+    for statement in statements {
+        new_statements.extend(reconstructor.reconstruct_statement(statement)?);
+    }
+    Ok(new_statements)
+}
+
+pub fn rebuild_statement<R: Reconstructor + ?Sized>(
+    reconstructor: &mut R,
+    statement: Statement,
+) -> Result<Vec<Statement>, R::Error> {
+    let statement = match statement {
+        Statement::Assign {
+            variable,
+            expression,
+            span,
+        } => Statement::Assign {
+            variable,
+            expression: reconstructor.reconstruct_expression(expression)?,
+            span,
+        },
+        Statement::Reassign {
+            variable,
+            expression,
+            span,
+        } => Statement::Reassign {
+            variable,
+            expression: reconstructor.reconstruct_expression(expression)?,
+            span,
+        },
+        Statement::Return { expression, span } => Statement::Return {
+            expression: reconstructor.reconstruct_expression(expression)?,
+            span,
+        },
+        Statement::Assert { expression, span } => Statement::Assert {
+            expression: reconstructor.reconstruct_expression(expression)?,
+            span,
+        },
+        Statement::If {
+            expression,
+            statements_a,
+            statements_b,
+            span,
+        } => Statement::If {
+            expression: reconstructor.reconstruct_expression(expression)?,
+            statements_a: reconstructor.reconstruct_branch(statements_a)?,
+            statements_b: reconstructor.reconstruct_branch(statements_b)?,
+            span,
+        },
+    };
+    Ok(vec![statement])
+}
+
+pub fn rebuild_expression<R: Reconstructor + ?Sized>(
+    reconstructor: &mut R,
+    expression: Expression,
+) -> Result<Expression, R::Error> {
+    grow_stack(|| match expression.into_parts() {
+        Parts::Binary(left, operator, right) => {
+            let left = reconstructor.reconstruct_expression(left)?;
+            let right = reconstructor.reconstruct_expression(right)?;
+            Ok(Expression::binary(left, operator, right))
+        }
+        Parts::Value(value) => Ok(Expression::Value(Box::new(
+            reconstructor.reconstruct_value(value)?,
+        ))),
+    })
+}
+
+pub fn rebuild_value<R: Reconstructor + ?Sized>(
+    reconstructor: &mut R,
+    value: Value,
+) -> Result<Value, R::Error> {
+    match value {
+        Value::Expression(expression) => Ok(Value::Expression(Box::new(
+            reconstructor.reconstruct_expression(*expression)?,
+        ))),
+        value => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use std::convert::Infallible;
+
+    //counts every identifier use, including inside branches
+    struct CountIdentifiers(usize);
+
+    impl Visitor for CountIdentifiers {
+        fn visit_value(&mut self, value: &Value) {
+            if let Value::Identifier(_) = value {
+                self.0 += 1;
+            }
+            walk_value(self, value)
+        }
+    }
+
+    //renames every use and binding of `a` to `z`
+    struct Rename;
+
+    impl Reconstructor for Rename {
+        type Error = Infallible;
+
+        fn reconstruct_statement(
+            &mut self,
+            statement: Statement,
+        ) -> Result<Vec<Statement>, Infallible> {
+            match statement {
+                Statement::Assign {
+                    variable,
+                    expression,
+                    span,
+                } if variable == "a" => Ok(vec![Statement::Assign {
+                    variable: Symbol::new("z"),
+                    expression: self.reconstruct_expression(expression)?,
+                    span,
+                }]),
+                statement => rebuild_statement(self, statement),
+            }
+        }
+
+        fn reconstruct_value(&mut self, value: Value) -> Result<Value, Infallible> {
+            match value {
+                Value::Identifier(name) if name == "a" => Ok(Value::Identifier(Symbol::new("z"))),
+                value => rebuild_value(self, value),
+            }
+        }
+    }
+
+    const SOURCE: &str = "function main(b: u8) {\n    let a = b * 2u8 + b;\n    if a > b {\n        let c = a;\n    } else {\n        let c = b;\n    }\n}";
+
+    #[test]
+    fn test_visitor_reaches_every_value() {
+        let program = parse(SOURCE).unwrap();
+        let mut counter = CountIdentifiers(0);
+        counter.visit_program(&program);
+        assert_eq!(counter.0, 6);
+    }
+
+    #[test]
+    fn test_reconstructor_rebuilds_every_node() {
+        let program = parse(SOURCE).unwrap();
+        let renamed = Rename.reconstruct_program(program).unwrap();
+        let expected = parse("function main(b: u8) {\n    let z = b * 2u8 + b;\n    if z > b {\n        let c = z;\n    } else {\n        let c = b;\n    }\n}").unwrap();
+        assert_eq!(renamed, expected);
+    }
+}
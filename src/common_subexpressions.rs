@@ -0,0 +1,603 @@
+use crate::ast::*;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+
+// Common subexpression elimination.
+//
+// The subexpressions of `a * b + c` are the whole expression and its operand
+// `a * b`, along with every parenthesized expression. Each
+// list of statements is searched for subexpressions computed more than once,
+// counting uses inside nested `if` branches after the first use. A repeated
+// subexpression is bound once to a fresh `let cseN = ...;` right before the
+// statement that first computes it, or reuses `x` when that statement is
+// `let x = ...;`, and every later occurrence reads the binding instead.
+//
+// The largest repeated subexpressions are bound first, and the parts inside
+// them only get their own binding when they are still repeated elsewhere. A
+// subexpression first computed inside a branch is bound in that branch, so
+// nothing is evaluated on a path that did not evaluate it before. Expressions
+// that read a reassigned variable are left alone, since their value may
+// differ between two occurrences.
+
+//takes in a checked Program AST and returns it with repeated subexpressions computed once
+pub fn eliminate_common_subexpressions(program: Program) -> Program {
+    let mut names = Names::default();
+    names.visit_program(&program);
+    let mut eliminator = Eliminator {
+        reassigned: names.reassigned,
+        bound: names.bound,
+        next: 0,
+        available: HashMap::new(),
+        added: Vec::new(),
+    };
+    let Ok(program) = eliminator.reconstruct_program(program);
+    program
+}
+
+// Reconstructor that binds the repeated subexpressions of each list of statements and rewrites
+// their occurrences.
+struct Eliminator {
+    //names that are reassigned somewhere in the function
+    reassigned: HashSet<Symbol>,
+    //names bound anywhere in the function, which fresh names avoid
+    bound: HashSet<Symbol>,
+    //number of the next fresh name
+    next: usize,
+    //subexpressions that have been bound, by hash, with the name holding their value
+    available: HashMap<u64, Vec<(Expression, Symbol)>>,
+    //hashes in the order they were made available, to drop the bindings of a branch after it
+    added: Vec<u64>,
+}
+
+//the structure of an expression as far as equality goes
+#[derive(Clone, Copy)]
+struct Fingerprint {
+    hash: u64,
+    //number of operations, values and parentheses, which orders subexpressions by size
+    size: usize,
+    //whether it reads a reassigned name
+    reassigned: bool,
+}
+
+//a subexpression that may be bound
+struct Node<'a> {
+    fingerprint: Fingerprint,
+    expression: &'a Expression,
+}
+
+//the occurrences of a subexpression in a list of statements
+struct Occurrences<'a> {
+    expression: &'a Expression,
+    size: usize,
+    count: usize,
+    //index of the statement that first computes it
+    first: usize,
+    //the variable of the `let` the first occurrence is the whole expression of
+    whole: Option<Symbol>,
+}
+
+//a repeated subexpression and where it is bound
+struct Binding {
+    expression: Expression,
+    size: usize,
+    first: usize,
+    name: Symbol,
+    fresh: bool,
+}
+
+impl Eliminator {
+    fn fresh(&mut self) -> Symbol {
+        loop {
+            let name = Symbol::new(&format!("cse{}", self.next));
+            self.next += 1;
+            if !self.bound.contains(&name) {
+                return name;
+            }
+        }
+    }
+
+    fn lookup(&self, hash: u64, expression: &Expression) -> Option<Symbol> {
+        self.available
+            .get(&hash)?
+            .iter()
+            .rev()
+            .find(|(bound, _)| bound == expression)
+            .map(|(_, name)| name.clone())
+    }
+
+    fn make_available(&mut self, expression: Expression, name: Symbol) {
+        let hash = self
+            .fingerprint(&expression, &mut Vec::new(), &mut Vec::new())
+            .hash;
+        self.available
+            .entry(hash)
+            .or_default()
+            .push((expression, name));
+        self.added.push(hash);
+    }
+
+    //fingerprints every node of an expression into `order`, the operands before the operation,
+    //and returns the fingerprint of the whole. Pushes every subexpression that could be bound
+    //onto `nodes`, leaving out those that are bound already and the parts inside them.
+    fn fingerprint<'a>(
+        &self,
+        expression: &'a Expression,
+        nodes: &mut Vec<Node<'a>>,
+        order: &mut Vec<Fingerprint>,
+    ) -> Fingerprint {
+        grow_stack(|| {
+            let start = nodes.len();
+            let fingerprint = match expression {
+                Expression::Binary {
+                    left,
+                    operator,
+                    right,
+                } => {
+                    let left = self.fingerprint(left, nodes, order);
+                    let right = self.fingerprint(right, nodes, order);
+                    let fingerprint = Fingerprint {
+                        hash: hash_of((left.hash, std::mem::discriminant(operator), right.hash)),
+                        size: left.size + right.size + 1,
+                        reassigned: left.reassigned || right.reassigned,
+                    };
+                    if !fingerprint.reassigned {
+                        if self.lookup(fingerprint.hash, expression).is_some() {
+                            //everything pushed for this expression so far is part of it
+                            nodes.truncate(start);
+                        } else {
+                            nodes.push(Node {
+                                fingerprint,
+                                expression,
+                            });
+                        }
+                    }
+                    fingerprint
+                }
+                Expression::Value(value) => self.fingerprint_value(value, nodes, order),
+            };
+            order.push(fingerprint);
+            fingerprint
+        })
+    }
+
+    fn fingerprint_value<'a>(
+        &self,
+        value: &'a Value,
+        nodes: &mut Vec<Node<'a>>,
+        order: &mut Vec<Fingerprint>,
+    ) -> Fingerprint {
+        match value {
+            Value::Expression(inner) => {
+                let inner = self.fingerprint(inner, nodes, order);
+                Fingerprint {
+                    hash: hash_of(("()", inner.hash)),
+                    size: inner.size + 1,
+                    reassigned: inner.reassigned,
+                }
+            }
+            Value::Identifier(name) => Fingerprint {
+                hash: hash_of(name.as_str()),
+                size: 1,
+                reassigned: self.reassigned.contains(name),
+            },
+            value => Fingerprint {
+                hash: hash_of(value.to_string()),
+                size: 1,
+                reassigned: false,
+            },
+        }
+    }
+
+    //replaces the bound subexpressions of an expression, given the fingerprints of its nodes in
+    //`order` with the one of the expression itself at `end`
+    fn replace(&self, expression: Expression, order: &[Fingerprint], end: usize) -> Expression {
+        grow_stack(|| {
+            if matches!(expression, Expression::Binary { .. }) {
+                if let Some(name) = self.lookup(order[end].hash, &expression) {
+                    return Expression::Value(Box::new(Value::Identifier(name)));
+                }
+            }
+            match expression.into_parts() {
+                Parts::Binary(left, operator, right) => {
+                    let right_end = end - 1;
+                    let left_end = right_end - order[right_end].size;
+                    let left = self.replace(left, order, left_end);
+                    let right = self.replace(right, order, right_end);
+                    Expression::binary(left, operator, right)
+                }
+                //parentheses around a bound expression are left holding a single name
+                Parts::Value(Value::Expression(inner)) => {
+                    match self.replace(*inner, order, end - 1) {
+                        inner @ Expression::Value(_) => inner,
+                        inner => Expression::Value(Box::new(Value::Expression(Box::new(inner)))),
+                    }
+                }
+                Parts::Value(value) => Expression::Value(Box::new(value)),
+            }
+        })
+    }
+
+    //the subexpressions of a list of statements to bind, in the order they are found
+    fn plan(&mut self, statements: &[Statement]) -> Vec<Binding> {
+        let mut occurrences: HashMap<u64, Vec<Occurrences>> = HashMap::new();
+        for (index, statement) in statements.iter().enumerate() {
+            let mut nodes = Vec::new();
+            let expression = match statement {
+                Statement::Assign { expression, .. }
+                | Statement::Reassign { expression, .. }
+                | Statement::Return { expression, .. }
+                | Statement::Assert { expression, .. }
+                | Statement::If { expression, .. } => expression,
+            };
+            self.fingerprint(expression, &mut nodes, &mut Vec::new());
+            //the whole expression of a `let` can be read from its variable
+            let whole = match statement {
+                Statement::Assign { variable, .. } if !self.reassigned.contains(variable) => {
+                    Some(variable.clone())
+                }
+                _ => None,
+            };
+            for node in nodes {
+                let whole = whole
+                    .clone()
+                    .filter(|_| std::ptr::eq(node.expression, expression));
+                count(&mut occurrences, node, Some((index, whole)));
+            }
+            if let Statement::If {
+                statements_a,
+                statements_b,
+                ..
+            } = statement
+            {
+                let mut nodes = Vec::new();
+                self.nested(statements_a, &mut nodes);
+                self.nested(statements_b, &mut nodes);
+                for node in nodes {
+                    count(&mut occurrences, node, None);
+                }
+            }
+        }
+
+        let mut repeated: Vec<(u64, usize)> = occurrences
+            .iter()
+            .flat_map(|(hash, entries)| {
+                entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.count > 1)
+                    .map(|(i, _)| (*hash, i))
+            })
+            .collect();
+        repeated.sort_by_key(|(hash, i)| {
+            let entry = &occurrences[hash][*i];
+            (Reverse(entry.size), entry.first)
+        });
+        let mut chosen = Vec::new();
+        for (hash, i) in repeated {
+            let entry = &occurrences[&hash][i];
+            if entry.count < 2 {
+                continue;
+            }
+            let (expression, count) = (entry.expression, entry.count);
+            chosen.push((hash, i));
+            //occurrences inside this one are replaced along with it, except those in its binding
+            let mut nodes = Vec::new();
+            self.fingerprint(expression, &mut nodes, &mut Vec::new());
+            for node in nodes {
+                if std::ptr::eq(node.expression, expression) {
+                    continue;
+                }
+                if let Some(inner) =
+                    occurrences
+                        .get_mut(&node.fingerprint.hash)
+                        .and_then(|entries| {
+                            entries
+                                .iter_mut()
+                                .find(|entry| entry.expression == node.expression)
+                        })
+                {
+                    inner.count = inner.count.saturating_sub(count - 1);
+                }
+            }
+        }
+
+        let mut bindings = Vec::with_capacity(chosen.len());
+        for (hash, i) in chosen {
+            let entry = &occurrences[&hash][i];
+            let (name, fresh) = match &entry.whole {
+                Some(variable) => (variable.clone(), false),
+                None => (self.fresh(), true),
+            };
+            bindings.push(Binding {
+                expression: entry.expression.clone(),
+                size: entry.size,
+                first: entry.first,
+                name,
+                fresh,
+            });
+        }
+        //the parts inside a subexpression are bound before it
+        bindings.sort_by_key(|binding| (binding.first, binding.size));
+        bindings
+    }
+
+    //pushes the subexpressions of statements nested in a branch
+    fn nested<'a>(&self, statements: &'a [Statement], nodes: &mut Vec<Node<'a>>) {
+        for statement in statements {
+            match statement {
+                Statement::Assign { expression, .. }
+                | Statement::Reassign { expression, .. }
+                | Statement::Return { expression, .. }
+                | Statement::Assert { expression, .. } => {
+                    self.fingerprint(expression, nodes, &mut Vec::new());
+                }
+                Statement::If {
+                    expression,
+                    statements_a,
+                    statements_b,
+                    ..
+                } => {
+                    self.fingerprint(expression, nodes, &mut Vec::new());
+                    self.nested(statements_a, nodes);
+                    self.nested(statements_b, nodes);
+                }
+            }
+        }
+    }
+}
+
+//counts an occurrence of a subexpression, which is only the first if it was found at `first`
+fn count<'a>(
+    occurrences: &mut HashMap<u64, Vec<Occurrences<'a>>>,
+    node: Node<'a>,
+    first: Option<(usize, Option<Symbol>)>,
+) {
+    let entries = occurrences.entry(node.fingerprint.hash).or_default();
+    if let Some(entry) = entries
+        .iter_mut()
+        .find(|entry| entry.expression == node.expression)
+    {
+        entry.count += 1;
+    } else if let Some((first, whole)) = first {
+        entries.push(Occurrences {
+            expression: node.expression,
+            size: node.fingerprint.size,
+            count: 1,
+            first,
+            whole,
+        });
+    }
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Reconstructor for Eliminator {
+    type Error = Infallible;
+
+    fn reconstruct_statements(
+        &mut self,
+        statements: Vec<Statement>,
+    ) -> Result<Vec<Statement>, Infallible> {
+        let mut bindings = self.plan(&statements).into_iter().peekable();
+        let mut new_statements = Vec::with_capacity(statements.len());
+        for (index, statement) in statements.into_iter().enumerate() {
+            let mut existing = Vec::new();
+            while let Some(binding) = bindings.next_if(|binding| binding.first == index) {
+                if binding.fresh {
+                    let expression = self.reconstruct_expression(binding.expression.clone())?;
+                    //written where the statement that computed it was
+                    new_statements.push(Statement::Assign {
+                        variable: binding.name.clone(),
+                        expression,
+                        span: statement.span(),
+                    });
+                    self.make_available(binding.expression, binding.name);
+                } else {
+                    existing.push(binding);
+                }
+            }
+            new_statements.extend(self.reconstruct_statement(statement)?);
+            //a `let` holds its value only once it has been evaluated
+            for binding in existing {
+                self.make_available(binding.expression, binding.name);
+            }
+        }
+        Ok(new_statements)
+    }
+
+    fn reconstruct_branch(
+        &mut self,
+        statements: Vec<Statement>,
+    ) -> Result<Vec<Statement>, Infallible> {
+        let mark = self.added.len();
+        let statements = self.reconstruct_statements(statements);
+        //bindings made inside the branch end with it
+        for hash in self.added.split_off(mark) {
+            let entries = self.available.get_mut(&hash).unwrap();
+            entries.pop();
+            if entries.is_empty() {
+                self.available.remove(&hash);
+            }
+        }
+        statements
+    }
+
+    fn reconstruct_expression(&mut self, expression: Expression) -> Result<Expression, Infallible> {
+        if self.available.is_empty() {
+            return Ok(expression);
+        }
+        //the largest bound subexpressions are found first, from the root of the tree down
+        let mut order = Vec::new();
+        self.fingerprint(&expression, &mut Vec::new(), &mut order);
+        let end = order.len() - 1;
+        Ok(self.replace(expression, &order, end))
+    }
+}
+
+//the names bound and reassigned anywhere in a program
+#[derive(Default)]
+struct Names {
+    bound: HashSet<Symbol>,
+    reassigned: HashSet<Symbol>,
+}
+
+impl Visitor for Names {
+    fn visit_input(&mut self, input: &Input) {
+        self.bound.insert(input.name.clone());
+    }
+
+    fn visit_expression(&mut self, _expression: &Expression) {}
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Assign { variable, .. } => {
+                self.bound.insert(variable.clone());
+            }
+            Statement::Reassign { variable, .. } => {
+                self.reassigned.insert(variable.clone());
+            }
+            Statement::Return { .. } | Statement::Assert { .. } | Statement::If { .. } => {}
+        }
+        walk_statement(self, statement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leo;
+
+    #[test]
+    fn test_repeated_expressions_are_bound_once() {
+        let program = leo!(function main(a: u8, b: u8, c: u8) {
+            let d = a * b + c;
+            let e = (a * b + c) - 1u8;
+            return a * b + c;
+        });
+        let expected = leo!(function main(a: u8, b: u8, c: u8) {
+            let d = a * b + c;
+            let e = d - 1u8;
+            return d;
+        });
+        assert_eq!(eliminate_common_subexpressions(program), expected);
+    }
+
+    #[test]
+    fn test_fresh_bindings() {
+        let program = leo!(function main(a: u8, b: u8, c: u8) {
+            let d = c - (a + b);
+            let e = (a + b) * c;
+            let f = c * b + 1u8;
+            let g = c * b - a;
+            return d + e;
+        });
+        let expected = leo!(function main(a: u8, b: u8, c: u8) {
+            let cse0 = a + b;
+            let d = c - cse0;
+            let e = cse0 * c;
+            let cse1 = c * b;
+            let f = cse1 + 1u8;
+            let g = cse1 - a;
+            return d + e;
+        });
+        assert_eq!(eliminate_common_subexpressions(program), expected);
+    }
+
+    #[test]
+    fn test_only_operands_are_subexpressions() {
+        //`a * b + c` adds `c` to `a * b`, so it computes `a * b` but not `b + c`
+        let program = leo!(function main(a: u8, b: u8, c: u8) {
+            let d = a * b + c;
+            let e = b + c;
+            let f = a - b - c;
+            let g = b - c;
+            let h = a * b - 1u8;
+            return d + e + f + g + h;
+        });
+        let expected = leo!(function main(a: u8, b: u8, c: u8) {
+            let cse0 = a * b;
+            let d = cse0 + c;
+            let e = b + c;
+            let f = a - b - c;
+            let g = b - c;
+            let h = cse0 - 1u8;
+            return d + e + f + g + h;
+        });
+        assert_eq!(eliminate_common_subexpressions(program), expected);
+    }
+
+    #[test]
+    fn test_parts_repeated_elsewhere_are_bound_too() {
+        let program = leo!(function main(a: u8, b: u8) {
+            let c = (a + b) * (a + b);
+            let d = (a + b) * (a + b);
+            let e = b - (a + b);
+            return c + d + e;
+        });
+        let expected = leo!(function main(a: u8, b: u8) {
+            let cse0 = a + b;
+            let c = cse0 * cse0;
+            let d = c;
+            let e = b - cse0;
+            return c + d + e;
+        });
+        assert_eq!(eliminate_common_subexpressions(program), expected);
+    }
+
+    #[test]
+    fn test_branches() {
+        let program = leo!(function main(a: u8, b: u8, flag: bool) {
+            let c = a * b;
+            if flag {
+                let d = a * b;
+                let e = b - 1u8;
+                let f = (b - 1u8) * a;
+            } else {
+                let d = a + 1u8;
+            }
+            let g = (a + 1u8) * 2u8;
+            return c;
+        });
+        let expected = leo!(function main(a: u8, b: u8, flag: bool) {
+            let c = a * b;
+            if flag {
+                let d = c;
+                let e = b - 1u8;
+                let f = e * a;
+            } else {
+                let d = a + 1u8;
+            }
+            let g = (a + 1u8) * 2u8;
+            return c;
+        });
+        assert_eq!(eliminate_common_subexpressions(program), expected);
+    }
+
+    #[test]
+    fn test_reassigned_names_are_left_alone() {
+        let program = leo!(function main(a: u8, b: u8) {
+            let c = a + b;
+            a = 1u8;
+            let d = a + b;
+            let e = b * 2u8;
+            let f = b * 2u8;
+            return c + d + e + f;
+        });
+        let expected = leo!(function main(a: u8, b: u8) {
+            let c = a + b;
+            a = 1u8;
+            let d = a + b;
+            let e = b * 2u8;
+            let f = e;
+            return c + d + e + f;
+        });
+        assert_eq!(eliminate_common_subexpressions(program), expected);
+    }
+}
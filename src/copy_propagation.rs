@@ -0,0 +1,194 @@
+use crate::ast::*;
+use crate::symbol_table::SymbolTable;
+use std::convert::Infallible;
+
+// Copy propagation of bindings like `let d = a;`.
+//
+// After such a binding `d` is an alias of `a`, and every later use of `d` is
+// replaced with `a`, including inside nested `if` branches. An alias of an
+// alias is resolved to the original name, so after `let e = d;` uses of `e`
+// become `a` as well. The alias only holds while both names keep the values
+// they had at the binding, so it ends at the first reassignment of either,
+// and a reassignment inside a branch ends it for the rest of the function.
+// The binding itself is left in place for dead code elimination to remove
+// once nothing reads it.
+
+//takes in a checked Program AST and returns it with uses of aliases replaced by their source
+pub fn propagate_copies(program: Program) -> Program {
+    let mut propagator = CopyPropagator {
+        copies: SymbolTable::new(),
+    };
+    let Ok(program) = propagator.reconstruct_program(program);
+    program
+}
+
+// Reconstructor that rewrites the uses of aliases, keeping the source of every visible name
+// that is currently an alias.
+struct CopyPropagator {
+    copies: SymbolTable<Option<Symbol>>,
+}
+
+impl CopyPropagator {
+    fn declare(&mut self, name: Symbol, source: Option<Symbol>) {
+        //a checked program never binds a name that is already visible
+        let _ = self.copies.declare(name, source);
+    }
+
+    //ends every alias involving a name whose value changes
+    fn reassign(&mut self, name: Symbol) {
+        let aliases: Vec<Symbol> = self
+            .copies
+            .visible()
+            .filter(|(alias, source)| **alias == name || source.as_ref() == Some(&name))
+            .map(|(alias, _)| alias.clone())
+            .collect();
+        for alias in aliases {
+            if let Some(source) = self.copies.lookup_mut(&alias) {
+                *source = None;
+            }
+        }
+    }
+}
+
+impl Reconstructor for CopyPropagator {
+    type Error = Infallible;
+
+    fn reconstruct_program(&mut self, program: Program) -> Result<Program, Infallible> {
+        for input in &program.inputs {
+            self.declare(input.name.clone(), None);
+        }
+        rebuild_program(self, program)
+    }
+
+    fn reconstruct_branch(
+        &mut self,
+        statements: Vec<Statement>,
+    ) -> Result<Vec<Statement>, Infallible> {
+        self.copies.enter_scope();
+        let statements = self.reconstruct_statements(statements);
+        self.copies.exit_scope();
+        statements
+    }
+
+    fn reconstruct_statement(
+        &mut self,
+        statement: Statement,
+    ) -> Result<Vec<Statement>, Infallible> {
+        let statements = rebuild_statement(self, statement)?;
+        for statement in &statements {
+            match statement {
+                Statement::Assign {
+                    variable,
+                    expression,
+                    ..
+                } => {
+                    //uses in the expression are already rewritten, so this is the original name
+                    let source = match expression {
+                        Expression::Value(value) => match &**value {
+                            Value::Identifier(source) => Some(source.clone()),
+                            _ => None,
+                        },
+                        Expression::Binary { .. } => None,
+                    };
+                    self.declare(variable.clone(), source);
+                }
+                Statement::Reassign { variable, .. } => self.reassign(variable.clone()),
+                Statement::Return { .. } | Statement::Assert { .. } | Statement::If { .. } => {}
+            }
+        }
+        Ok(statements)
+    }
+
+    fn reconstruct_value(&mut self, value: Value) -> Result<Value, Infallible> {
+        match value {
+            Value::Identifier(name) => match self.copies.lookup(&name) {
+                Some(Some(source)) => Ok(Value::Identifier(source.clone())),
+                _ => Ok(Value::Identifier(name)),
+            },
+            value => rebuild_value(self, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leo;
+
+    #[test]
+    fn test_uses_of_aliases_are_replaced() {
+        let program = leo!(function main(a: u8, flag: bool) {
+            let d = a;
+            let e = d;
+            let f = (e + 1u8) * d;
+            if flag {
+                let g = e;
+            } else {
+                let g = d == a;
+            }
+            return e;
+        });
+        let expected = leo!(function main(a: u8, flag: bool) {
+            let d = a;
+            let e = a;
+            let f = (a + 1u8) * a;
+            if flag {
+                let g = a;
+            } else {
+                let g = a == a;
+            }
+            return a;
+        });
+        assert_eq!(propagate_copies(program), expected);
+    }
+
+    #[test]
+    fn test_reassignment_ends_the_alias() {
+        let program = leo!(function main(a: u8, b: u8) {
+            let d = a;
+            let e = b;
+            let f = d + e;
+            a = 1u8;
+            e = 2u8;
+            let g = d + e;
+            return g;
+        });
+        let expected = leo!(function main(a: u8, b: u8) {
+            let d = a;
+            let e = b;
+            let f = a + b;
+            a = 1u8;
+            e = 2u8;
+            let g = d + e;
+            return g;
+        });
+        assert_eq!(propagate_copies(program), expected);
+    }
+
+    #[test]
+    fn test_reassignment_in_a_branch_ends_the_alias() {
+        let program = leo!(function main(a: u8, flag: bool) {
+            let d = a;
+            if flag {
+                let e = d;
+                a = 1u8;
+                let f = d;
+            } else {
+                let e = d;
+            }
+            return d;
+        });
+        let expected = leo!(function main(a: u8, flag: bool) {
+            let d = a;
+            if flag {
+                let e = a;
+                a = 1u8;
+                let f = d;
+            } else {
+                let e = d;
+            }
+            return d;
+        });
+        assert_eq!(propagate_copies(program), expected);
+    }
+}
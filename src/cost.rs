@@ -0,0 +1,212 @@
+use crate::ast::*;
+use crate::error::CompilerError;
+use crate::symbol_table::SymbolTable;
+use crate::typechecker::{infer_binary, infer_expression, infer_value};
+
+type Result<T> = std::result::Result<T, CompilerError>;
+
+// Estimated number of circuit constraints a program compiles to.
+//
+// Leo programs become arithmetic circuits, where the cost of an operation is
+// the number of constraints it adds rather than a number of instructions:
+//
+//   field    +, -  0       *  1       /  2       ==  2
+//   scalar   +, -  1       *  3       /  4       ==  2
+//   group    +, -  6                             ==  2
+//   u8       +, -  n + 1   *  2n + 1  /  3n + 2  ==  2   <, >  n + 1
+//
+// with n the number of bits of the integer type. Field additions are linear
+// and free, integer operations decompose their result into bits to check the
+// range, and a division constrains a quotient and a remainder. Multiplying or
+// dividing a field or scalar by a constant only scales a linear combination,
+// so it costs as much as an addition of the type, e.g. `a * 2field` and
+// `a / 4field` are free. Integer operations still check the range of their
+// result, so a constant operand does not make them cheaper. The numbers are
+// estimates to rank alternatives, not what a particular backend generates.
+// Both branches of an `if` are part of the circuit, so both count.
+
+//the estimated constraints of applying an operator to two operands of the given type, one of
+//which is a constant if `constant_operand` is set
+pub fn operation_cost(operator: &Operator, operand_type: &Type, constant_operand: bool) -> u64 {
+    let scaling = constant_operand && matches!(operator, Operator::Multiply | Operator::Divide);
+    match operand_type {
+        Type::U8 => {
+            let bits = u64::from(IntegerType::U8.bits());
+            match operator {
+                Operator::Add | Operator::Subtract | Operator::GreaterThan | Operator::LessThan => {
+                    bits + 1
+                }
+                Operator::Multiply => 2 * bits + 1,
+                Operator::Divide => 3 * bits + 2,
+                Operator::Equal => 2,
+            }
+        }
+        Type::Field => match operator {
+            _ if scaling => 0,
+            Operator::Add | Operator::Subtract => 0,
+            Operator::Multiply => 1,
+            _ => 2,
+        },
+        Type::Scalar => match operator {
+            _ if scaling => 1,
+            Operator::Add | Operator::Subtract => 1,
+            Operator::Multiply => 3,
+            Operator::Divide => 4,
+            _ => 2,
+        },
+        Type::Group => match operator {
+            Operator::Add | Operator::Subtract => 6,
+            _ => 2,
+        },
+        Type::Bool => 1,
+        Type::Address => 2,
+    }
+}
+
+//the estimated constraints of evaluating every operation in an expression
+pub fn expression_cost(expression: &Expression, types: &SymbolTable<Type>) -> Result<u64> {
+    Ok(costed(expression, types)?.0)
+}
+
+//the cost of an expression along with its type, which the operation it is an operand of costs by
+fn costed(expression: &Expression, types: &SymbolTable<Type>) -> Result<(u64, Type)> {
+    grow_stack(|| match expression {
+        Expression::Binary {
+            left,
+            operator,
+            right,
+        } => {
+            let (left_cost, left_type) = costed(left, types)?;
+            let (right_cost, right_type) = costed(right, types)?;
+            let constant_operand = is_constant(left) || is_constant(right);
+            let cost =
+                operation_cost(operator, &left_type, constant_operand) + left_cost + right_cost;
+            Ok((cost, infer_binary(operator, left_type, right_type)?))
+        }
+        Expression::Value(value) => match &**value {
+            Value::Expression(inner) => costed(inner, types),
+            value => Ok((0, infer_value(value, types)?)),
+        },
+    })
+}
+
+//whether an expression is a literal, which a folded program has for every constant operand
+fn is_constant(expression: &Expression) -> bool {
+    expression
+        .as_value()
+        .is_some_and(|value| !matches!(value, Value::Identifier(_) | Value::Expression(_)))
+}
+
+//the estimated constraints of a statement, declaring the name it binds in `types`
+pub fn statement_cost(statement: &Statement, types: &mut SymbolTable<Type>) -> Result<u64> {
+    match statement {
+        Statement::Assign {
+            variable,
+            expression,
+            ..
+        } => {
+            let cost = expression_cost(expression, types)?;
+            types.bind(variable.clone(), infer_expression(expression, types)?)?;
+            Ok(cost)
+        }
+        Statement::Reassign { expression, .. }
+        | Statement::Return { expression, .. }
+        | Statement::Assert { expression, .. } => expression_cost(expression, types),
+        Statement::If {
+            expression,
+            statements_a,
+            statements_b,
+            ..
+        } => {
+            let mut cost = expression_cost(expression, types)?;
+            for statements in [statements_a, statements_b] {
+                types.enter_scope();
+                let branch = statements_cost(statements, types);
+                types.exit_scope();
+                cost += branch?;
+            }
+            Ok(cost)
+        }
+    }
+}
+
+fn statements_cost(statements: &[Statement], types: &mut SymbolTable<Type>) -> Result<u64> {
+    let mut cost = 0;
+    for statement in statements {
+        cost += statement_cost(statement, types)?;
+    }
+    Ok(cost)
+}
+
+//the estimated constraints of a checked program
+pub fn program_cost(program: &Program) -> Result<u64> {
+    let mut types = SymbolTable::new();
+    for input in &program.inputs {
+        types.bind(input.name.clone(), input.input_type.clone())?;
+    }
+    statements_cost(&program.statements, &mut types)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leo;
+
+    #[test]
+    fn test_operations_cost_more_on_integers() {
+        assert_eq!(operation_cost(&Operator::Add, &Type::Field, false), 0);
+        assert_eq!(operation_cost(&Operator::Add, &Type::U8, false), 9);
+        assert_eq!(operation_cost(&Operator::Multiply, &Type::U8, false), 17);
+        assert_eq!(operation_cost(&Operator::Equal, &Type::U8, false), 2);
+    }
+
+    #[test]
+    fn test_scaling_by_constants() {
+        assert_eq!(operation_cost(&Operator::Multiply, &Type::Field, true), 0);
+        assert_eq!(operation_cost(&Operator::Divide, &Type::Field, true), 0);
+        assert_eq!(operation_cost(&Operator::Multiply, &Type::Scalar, true), 1);
+        assert_eq!(operation_cost(&Operator::Equal, &Type::Field, true), 2);
+        assert_eq!(operation_cost(&Operator::Multiply, &Type::U8, true), 17);
+        let program = leo!(function main(a: field, b: field) {
+            let c = a * 2field + (b / 4field) * a;
+            let d = (a * b) * 3field;
+        });
+        //only multiplying two variables costs a constraint, once in each statement
+        assert_eq!(program_cost(&program), Ok(2));
+    }
+
+    #[test]
+    fn test_statement_costs() {
+        let program = leo!(function main(a: u8, b: u8, flag: bool) {
+            let c = a * b + 1u8;
+            if flag {
+                let d = (c + 1u8) < a;
+            } else {
+                let d = c == a;
+            }
+            return c;
+        });
+        let mut types = SymbolTable::new();
+        for input in &program.inputs {
+            types
+                .bind(input.name.clone(), input.input_type.clone())
+                .unwrap();
+        }
+        let costs: Vec<u64> = program
+            .statements
+            .iter()
+            .map(|statement| statement_cost(statement, &mut types).unwrap())
+            .collect();
+        //both branches count, the condition itself is a single value
+        assert_eq!(costs, vec![17 + 9, 9 + 9 + 2, 0]);
+        assert_eq!(program_cost(&program), Ok(17 + 9 + 9 + 9 + 2));
+    }
+
+    #[test]
+    fn test_field_arithmetic_is_cheap() {
+        let program = leo!(function main(a: field, b: field) {
+            let c = a * b + a - 1field;
+        });
+        assert_eq!(program_cost(&program), Ok(1));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::ast::*;
+use std::collections::HashSet;
+
+// Liveness based removal of bindings no output depends on.
+//
+// The outputs of a function are its `return` and `assert` statements and the
+// variables marked to be kept. Statements are walked backwards with the set
+// of names a later output may still read: a `let` or reassignment of a name
+// that is not in the set is dropped, otherwise the names its expression reads
+// join the set. The set before an `if` is what either branch may read, plus
+// the names in its condition. A `let` is also kept when a later reassignment
+// of the name is, even if the value it binds is never read.
+//
+// Leo checks every operation at run time, so a dead binding is only dropped
+// when evaluating it cannot fail, e.g. `let b = 4u8;` or `let c = a == b;`.
+// A binding like `let d = a + b;` is kept since the addition may overflow.
+// A function without any outputs is deliberately left unchanged: nothing in
+// it would be live, so every binding that cannot fail would go, and there is
+// no result to tell which one matters. `test_ident.leo` keeps `let a = 2u8;`
+// unless one of its names is kept or returned.
+
+//takes in a folded Program AST and returns it without the bindings that no output reads
+pub fn eliminate_dead_code(program: Program, keep: &[Symbol]) -> Program {
+    let liveness = Liveness {
+        keep: keep.iter().cloned().collect(),
+    };
+    if !liveness.has_outputs(&program) {
+        return program;
+    }
+    let mut live = Live {
+        read: liveness.keep.clone(),
+        bound: HashSet::new(),
+    };
+    Program {
+        statements: liveness.statements(program.statements, &mut live),
+        ..program
+    }
+}
+
+//the names statements after the current one depend on
+#[derive(Clone)]
+struct Live {
+    //names whose current value may be read
+    read: HashSet<Symbol>,
+    //names that are reassigned and need their `let`
+    bound: HashSet<Symbol>,
+}
+
+impl Live {
+    //what either of two paths depends on
+    fn extend(&mut self, other: Live) {
+        self.read.extend(other.read);
+        self.bound.extend(other.bound);
+    }
+}
+
+struct Liveness {
+    //names marked as outputs, whose bindings are never dropped
+    keep: HashSet<Symbol>,
+}
+
+impl Liveness {
+    fn has_outputs(&self, program: &Program) -> bool {
+        let mut outputs = Outputs {
+            keep: &self.keep,
+            found: false,
+        };
+        outputs.visit_program(program);
+        outputs.found
+    }
+
+    //the statements that are kept, given the names read after them. Updates `live` to the
+    //names read from before them.
+    fn statements(&self, statements: Vec<Statement>, live: &mut Live) -> Vec<Statement> {
+        let mut kept = Vec::with_capacity(statements.len());
+        for statement in statements.into_iter().rev() {
+            if let Some(statement) = self.statement(statement, live) {
+                kept.push(statement);
+            }
+        }
+        kept.reverse();
+        kept
+    }
+
+    fn statement(&self, statement: Statement, live: &mut Live) -> Option<Statement> {
+        match statement {
+            Statement::Assign {
+                ref variable,
+                ref expression,
+                ..
+            } => {
+                let used = live.read.contains(variable)
+                    || live.bound.contains(variable)
+                    || self.keep.contains(variable);
+                if !used && !can_fail(expression) {
+                    return None;
+                }
+                live.read.remove(variable);
+                live.bound.remove(variable);
+                read(expression, live);
+                Some(statement)
+            }
+            Statement::Reassign {
+                ref variable,
+                ref expression,
+                ..
+            } => {
+                let used = live.read.contains(variable) || self.keep.contains(variable);
+                if !used && !can_fail(expression) {
+                    return None;
+                }
+                //the value read later is the one written here
+                live.read.remove(variable);
+                live.bound.insert(variable.clone());
+                read(expression, live);
+                Some(statement)
+            }
+            Statement::Return { ref expression, .. } | Statement::Assert { ref expression, .. } => {
+                read(expression, live);
+                Some(statement)
+            }
+            Statement::If {
+                expression,
+                statements_a,
+                statements_b,
+                span,
+            } => {
+                let mut live_b = live.clone();
+                let statements_a = self.statements(statements_a, live);
+                let statements_b = self.statements(statements_b, &mut live_b);
+                live.extend(live_b);
+                if statements_a.is_empty() && statements_b.is_empty() && !can_fail(&expression) {
+                    return None;
+                }
+                read(&expression, live);
+                Some(Statement::If {
+                    expression,
+                    statements_a,
+                    statements_b,
+                    span,
+                })
+            }
+        }
+    }
+}
+
+//adds the names an expression reads to `live`
+fn read(expression: &Expression, live: &mut Live) {
+    Reads(&mut live.read).visit_expression(expression)
+}
+
+//whether evaluating an expression may fail at run time, conservatively any arithmetic
+fn can_fail(expression: &Expression) -> bool {
+    let mut arithmetic = Arithmetic(false);
+    arithmetic.visit_expression(expression);
+    arithmetic.0
+}
+
+//collects the identifiers read by the visited expressions
+struct Reads<'a>(&'a mut HashSet<Symbol>);
+
+impl Visitor for Reads<'_> {
+    fn visit_value(&mut self, value: &Value) {
+        if let Value::Identifier(name) = value {
+            self.0.insert(name.clone());
+        }
+        walk_value(self, value)
+    }
+}
+
+//finds whether the visited expressions contain an arithmetic operator
+struct Arithmetic(bool);
+
+impl Visitor for Arithmetic {
+    fn visit_operator(&mut self, operator: &Operator) {
+        if matches!(
+            operator,
+            Operator::Add | Operator::Subtract | Operator::Multiply | Operator::Divide
+        ) {
+            self.0 = true;
+        }
+    }
+}
+
+//finds whether the visited statements contain a return, an assertion or a kept binding
+struct Outputs<'a> {
+    keep: &'a HashSet<Symbol>,
+    found: bool,
+}
+
+impl Visitor for Outputs<'_> {
+    fn visit_expression(&mut self, _expression: &Expression) {}
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Return { .. } | Statement::Assert { .. } => self.found = true,
+            Statement::Assign { variable, .. } | Statement::Reassign { variable, .. } => {
+                self.found |= self.keep.contains(variable)
+            }
+            Statement::If { .. } => {}
+        }
+        walk_statement(self, statement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leo;
+
+    #[test]
+    fn test_unused_bindings_are_removed() {
+        let program = leo!(function main() {
+            let a = 2u8;
+            let b = 4u8;
+            let c = 6u8;
+            return c;
+        });
+        let expected = leo!(function main() {
+            let c = 6u8;
+            return c;
+        });
+        assert_eq!(eliminate_dead_code(program, &[]), expected);
+    }
+
+    #[test]
+    fn test_bindings_read_by_outputs_are_kept() {
+        let program = leo!(function main(a: u8) {
+            let b = a == 1u8;
+            let c = b;
+            let d = a;
+            assert(c);
+            let e = d;
+            return e;
+        });
+        assert_eq!(eliminate_dead_code(program.clone(), &[]), program);
+    }
+
+    #[test]
+    fn test_kept_names_are_outputs() {
+        let program = leo!(function main() {
+            let a = 2u8;
+            let b = 4u8;
+            let c = 6u8;
+        });
+        let expected = leo!(function main() {
+            let c = 6u8;
+        });
+        assert_eq!(eliminate_dead_code(program, &[Symbol::new("c")]), expected);
+    }
+
+    #[test]
+    fn test_without_outputs_nothing_is_removed() {
+        let program = leo!(function main() {
+            let a = 2u8;
+            let b = a;
+        });
+        assert_eq!(eliminate_dead_code(program.clone(), &[]), program);
+    }
+
+    #[test]
+    fn test_bindings_that_may_fail_are_kept() {
+        let program = leo!(function main(a: u8) {
+            let b = a + 1u8;
+            let c = a < 1u8;
+            return a;
+        });
+        let expected = leo!(function main(a: u8) {
+            let b = a + 1u8;
+            return a;
+        });
+        assert_eq!(eliminate_dead_code(program, &[]), expected);
+    }
+
+    #[test]
+    fn test_overwritten_values_are_dead() {
+        let program = leo!(function main(a: u8) {
+            let b = 1u8;
+            b = 2u8;
+            b = a;
+            let c = b;
+            c = 3u8;
+            return b;
+        });
+        //`let b = 1u8;` is overwritten before it is read, but the binding has to stay for `b = a;`
+        let expected = leo!(function main(a: u8) {
+            let b = 1u8;
+            b = a;
+            return b;
+        });
+        assert_eq!(eliminate_dead_code(program, &[]), expected);
+    }
+
+    #[test]
+    fn test_branches() {
+        let program = leo!(function main(flag: bool, a: u8) {
+            let b = 1u8;
+            let c = 2u8;
+            if flag {
+                let d = 3u8;
+                b = a;
+            } else {
+                let d = c;
+            }
+            return b;
+        });
+        let expected = leo!(function main(flag: bool, a: u8) {
+            let b = 1u8;
+            if flag {
+                b = a;
+            } else {
+            }
+            return b;
+        });
+        assert_eq!(eliminate_dead_code(program, &[]), expected);
+    }
+}
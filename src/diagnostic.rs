@@ -0,0 +1,155 @@
+pub use crate::ast::Span;
+use crate::error::{CompilerWarning, GenerationError};
+use pest::error::LineColLocation;
+use serde::Serialize;
+use std::path::Path;
+
+// Structured diagnostics for `--message-format json`. Every error or warning
+// reported by `generate` is turned into one `Diagnostic`, which serializes to
+// a single JSON object (see the README for the schema).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Note {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub span: Option<Span>,
+    pub notes: Vec<Note>,
+}
+
+impl Diagnostic {
+    //builds an error diagnostic, attaching the source position for parse errors and errors
+    //raised in a statement of the source
+    pub fn from_error(error: &GenerationError, file: Option<&Path>) -> Diagnostic {
+        let (message, span) = match error {
+            GenerationError::ParseError(e) => {
+                let span = match e.line_col {
+                    LineColLocation::Pos((line, column)) => Span {
+                        start_line: line,
+                        start_column: column,
+                        end_line: line,
+                        end_column: column,
+                    },
+                    LineColLocation::Span((start_line, start_column), (end_line, end_column)) => {
+                        Span {
+                            start_line,
+                            start_column,
+                            end_line,
+                            end_column,
+                        }
+                    }
+                };
+                (e.variant.message().to_string(), Some(span))
+            }
+            GenerationError::CompilerError(e) => (e.to_string(), e.span()),
+            _ => (error.to_string(), None),
+        };
+
+        Diagnostic {
+            code: error.code(),
+            severity: Severity::Error,
+            message,
+            file: file.map(|path| path.display().to_string()),
+            span,
+            notes: error
+                .notes()
+                .into_iter()
+                .map(|message| Note {
+                    message,
+                    span: None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn from_warning(warning: &CompilerWarning, file: Option<&Path>) -> Diagnostic {
+        let notes = match warning {
+            CompilerWarning::ConstantCondition(value) => vec![Note {
+                message: format!(
+                    "only the {} branch is kept in the optimized output",
+                    if *value { "if" } else { "else" }
+                ),
+                span: None,
+            }],
+            CompilerWarning::IterationLimit(_) => vec![Note {
+                message: String::from("the limit can be raised with `--max-iterations`"),
+                span: None,
+            }],
+        };
+
+        Diagnostic {
+            code: warning.code(),
+            severity: Severity::Warning,
+            message: warning.to_string(),
+            file: file.map(|path| path.display().to_string()),
+            span: None,
+            notes,
+        }
+    }
+
+    //serializes the diagnostic as a single line of JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("diagnostics are always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CompilerError;
+    use crate::{compile, parse};
+
+    #[test]
+    fn test_parse_error_json() {
+        let error =
+            GenerationError::from(parse("function main() {\n    let a = ;\n}").unwrap_err());
+        let diagnostic = Diagnostic::from_error(&error, Some(Path::new("bad.leo")));
+        let json: serde_json::Value = serde_json::from_str(&diagnostic.to_json()).unwrap();
+
+        assert_eq!(json["code"], "E0003");
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["file"], "bad.leo");
+        assert_eq!(json["span"]["start_line"], 2);
+        assert!(json["notes"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_located_compiler_error_json() {
+        let source = "function main(a: u8) {\n    let b = 1u8;\n    let c = 200u8 * 2u8 + a;\n}";
+        let error = compile(source).unwrap_err();
+        let diagnostic = Diagnostic::from_error(&error, None);
+        let json: serde_json::Value = serde_json::from_str(&diagnostic.to_json()).unwrap();
+
+        assert_eq!(json["code"], "E0102");
+        assert_eq!(json["message"], "Integer overflow during evaluation");
+        assert_eq!(
+            json["span"],
+            serde_json::json!({"start_line": 3, "start_column": 5, "end_line": 3, "end_column": 27})
+        );
+    }
+
+    #[test]
+    fn test_compiler_error_json() {
+        let error = GenerationError::CompilerError(CompilerError::Overflow);
+        let diagnostic = Diagnostic::from_error(&error, None);
+
+        assert_eq!(
+            diagnostic.to_json(),
+            r#"{"code":"E0102","severity":"error","message":"Integer overflow during evaluation","file":null,"span":null,"notes":[]}"#
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ast::{Operator, SourceSpan, Span, Type};
 use crate::Rule;
 use pest::error::Error as PestError;
 use std::fmt;
@@ -8,26 +9,126 @@ pub enum CompilerError {
     Overflow,
     DivByZero,
     MismatchType,
+    UndefinedVariable {
+        name: String,
+        similar: Option<(String, Binding)>,
+    },
+    TypeMismatch {
+        operator: Operator,
+        left: Type,
+        right: Type,
+    },
+    NonBooleanCondition(Type),
+    Redeclaration {
+        name: String,
+        previous: Option<Binding>,
+    },
+    ReassignmentMismatch {
+        name: String,
+        expected: Type,
+        found: Type,
+    },
+    NonBooleanAssertion(Type),
+    AssertionFailure,
+    // any of the above, raised in the statement written at the span
+    Located {
+        error: Box<CompilerError>,
+        span: Span,
+    },
+}
+
+// where a name was bound, used to point at similarly named variables
+#[derive(Debug, PartialEq, Clone)]
+pub enum Binding {
+    // function main(a: u8)
+    Input { function: String },
+    // let a = 1u8;
+    Local { statement: String },
 }
 
+#[derive(Debug, PartialEq)]
+pub enum CompilerWarning {
+    ConstantCondition(bool),
+    IterationLimit(usize),
+}
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq)]
 pub enum GenerationError {
     FileReadError,
-    FileWriteError,
+    FileWriteError(String),
     CompilerError(CompilerError),
-    ParseError(PestError<Rule>),
+    ParseError(Box<PestError<Rule>>),
+    AstFormatError(String),
+    WriteError(String),
 }
 
 // simple display for error variants related to compilation
 impl fmt::Display for CompilerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
+        match self {
             CompilerError::Underflow => write!(f, "Integer underflow during evaluation"),
             CompilerError::Overflow => write!(f, "Integer overflow during evaluation"),
             CompilerError::DivByZero => write!(f, "Division by Zero during evaluation"),
             CompilerError::MismatchType => {
                 write!(f, "Operation on mismatched types during evaluation")
             }
+            CompilerError::UndefinedVariable { name, .. } => {
+                write!(f, "Use of undefined variable `{}`", name)
+            }
+            CompilerError::TypeMismatch {
+                operator,
+                left,
+                right,
+            } => write!(
+                f,
+                "Operator `{}` cannot be applied to `{}` and `{}`",
+                operator, left, right
+            ),
+            CompilerError::NonBooleanCondition(found) => {
+                write!(
+                    f,
+                    "Condition of if statement must be `bool`, found `{}`",
+                    found
+                )
+            }
+            CompilerError::Redeclaration { name, .. } => write!(
+                f,
+                "Variable `{}` is already declared in this or an enclosing scope",
+                name
+            ),
+            CompilerError::ReassignmentMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Cannot assign a value of type `{}` to `{}`, which has type `{}`",
+                found, name, expected
+            ),
+            CompilerError::NonBooleanAssertion(found) => {
+                write!(f, "Assertion must be `bool`, found `{}`", found)
+            }
+            CompilerError::AssertionFailure => {
+                write!(f, "Assertion always fails during evaluation")
+            }
+            CompilerError::Located { error, .. } => write!(f, "{}", error),
+        }
+    }
+}
+
+// simple display for warnings raised while optimizing
+impl fmt::Display for CompilerWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompilerWarning::ConstantCondition(value) => {
+                write!(f, "Condition of if statement always evaluates to {}", value)
+            }
+            CompilerWarning::IterationLimit(iterations) => write!(
+                f,
+                "Optimization stopped after {} iterations without reaching a fixed point",
+                iterations
+            ),
         }
     }
 }
@@ -36,15 +137,134 @@ impl fmt::Display for GenerationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             GenerationError::FileReadError => write!(f, "Error reading file path"),
-            GenerationError::FileWriteError => write!(f, "Error writing file to path"),
+            GenerationError::FileWriteError(e) => write!(f, "Error writing file to path: {}", e),
             GenerationError::CompilerError(e) => write!(f, "{}", e),
             GenerationError::ParseError(e) => write!(f, "{}", e),
+            GenerationError::AstFormatError(e) => write!(f, "Error reading JSON AST: {}", e),
+            GenerationError::WriteError(e) => write!(f, "Error writing output: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CompilerError {}
+
+impl std::error::Error for GenerationError {}
+
+// stable diagnostic codes used by machine-readable output
+impl CompilerError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompilerError::Underflow => "E0101",
+            CompilerError::Overflow => "E0102",
+            CompilerError::DivByZero => "E0103",
+            CompilerError::MismatchType => "E0104",
+            CompilerError::UndefinedVariable { .. } => "E0105",
+            CompilerError::TypeMismatch { .. } => "E0106",
+            CompilerError::NonBooleanCondition(_) => "E0107",
+            CompilerError::Redeclaration { .. } => "E0108",
+            CompilerError::ReassignmentMismatch { .. } => "E0109",
+            CompilerError::NonBooleanAssertion(_) => "E0110",
+            CompilerError::AssertionFailure => "E0111",
+            CompilerError::Located { error, .. } => error.code(),
+        }
+    }
+
+    //the error raised in a statement, located at the statement unless a statement inside it
+    //located it already or the statement was not written in the source
+    pub fn in_statement(self, span: SourceSpan) -> CompilerError {
+        match (self, span) {
+            (error @ CompilerError::Located { .. }, _) | (error, SourceSpan(None)) => error,
+            (error, SourceSpan(Some(span))) => CompilerError::Located {
+                error: Box::new(error),
+                span,
+            },
+        }
+    }
+
+    //the error itself, without where it was raised
+    pub fn without_span(self) -> CompilerError {
+        match self {
+            CompilerError::Located { error, .. } => *error,
+            error => error,
+        }
+    }
+
+    //where in the source the error was raised, if known
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            CompilerError::Located { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    //additional context for the error, reported alongside the message
+    pub fn notes(&self) -> Vec<String> {
+        match self {
+            CompilerError::UndefinedVariable {
+                similar: Some((name, Binding::Input { function })),
+                ..
+            } => vec![format!(
+                "a similarly named input `{}` is declared by `function {}`",
+                name, function
+            )],
+            CompilerError::UndefinedVariable {
+                similar: Some((name, Binding::Local { statement })),
+                ..
+            } => vec![format!(
+                "a similarly named variable `{}` is bound by `{}`",
+                name, statement
+            )],
+            CompilerError::Redeclaration {
+                name,
+                previous: Some(Binding::Input { function }),
+            } => vec![format!(
+                "`{}` is declared as an input of `function {}`",
+                name, function
+            )],
+            CompilerError::Redeclaration {
+                name,
+                previous: Some(Binding::Local { statement }),
+            } => vec![format!(
+                "`{}` was previously bound by `{}`",
+                name, statement
+            )],
+            CompilerError::Located { error, .. } => error.notes(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl CompilerWarning {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            CompilerWarning::ConstantCondition(_) => "W0001",
+            CompilerWarning::IterationLimit(_) => "W0002",
+        }
+    }
+}
+
+impl GenerationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            GenerationError::FileReadError => "E0001",
+            GenerationError::FileWriteError(_) => "E0002",
+            GenerationError::ParseError(_) => "E0003",
+            GenerationError::AstFormatError(_) => "E0004",
+            GenerationError::WriteError(_) => "E0005",
+            GenerationError::CompilerError(e) => e.code(),
+        }
+    }
+
+    pub fn notes(&self) -> Vec<String> {
+        match self {
+            GenerationError::CompilerError(e) => e.notes(),
+            _ => Vec::new(),
         }
     }
 }
 
-impl From<PestError<Rule>> for GenerationError {
-    fn from(value: PestError<Rule>) -> Self {
+impl From<Box<PestError<Rule>>> for GenerationError {
+    fn from(value: Box<PestError<Rule>>) -> Self {
         GenerationError::ParseError(value)
     }
 }
@@ -0,0 +1,672 @@
+//! Constant folding for a small subset of the Leo language.
+//!
+//! A compilation runs in four steps, each of which is available on its own:
+//!
+//! 1. [`parse`] turns Leo source into a [`Program`], or a JSON AST is read with [`ast::json::from_json`].
+//! 2. [`resolve`] and [`typechecker::check`] reject undefined names and ill-typed expressions.
+//! 3. [`fold`] evaluates constant expressions and inlines `if` statements with a known condition,
+//!    [`eliminate_common_subexpressions`] binds expressions computed more than once to a `let`,
+//!    [`propagate_copies`] replaces uses of aliases like `let d = a;` with their source, and
+//!    [`simplify`] applies algebraic identities like `a * 1u8` to `a` around unknown values,
+//!    [`reduce_strength`] replaces operations with ones that [`cost`] estimates to need fewer
+//!    circuit constraints, like `a * 2u8` with `a + a`, and
+//!    [`eliminate_dead_code`] removes the bindings that no `return`, `assert` or kept variable
+//!    reads. A [`PassManager`] runs these passes until the program stops changing.
+//! 4. [`generate_code`] prints the folded program back as Leo source.
+//!
+//! [`optimize`] runs steps 2 and 3 together the same way the `generate` command does.
+//! [`compile`] runs the whole pipeline on a string, [`compile_to`] writes the result to any
+//! [`std::io::Write`] and [`generate`] goes from one file to another. Every step reports
+//! failures through the types in [`error`], which convert into [`GenerationError`].
+//!
+//! ```
+//! use coding_task_constant_folding::{generate_code, optimize, parse, GenerationError};
+//!
+//! fn main() -> Result<(), GenerationError> {
+//!     let program = parse("function main(a: u8) {\n    let b = 1u8 + 2u8;\n    let c = a + b;\n}")?;
+//!     let (folded, warnings) = optimize(program)?;
+//!     assert_eq!(
+//!         generate_code(&folded),
+//!         "function main(a: u8) {\n    let b = 3u8;\n    let c = a + b;\n}"
+//!     );
+//!     assert!(warnings.is_empty());
+//!     Ok(())
+//! }
+//! ```
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
+
+pub mod ast;
+pub use ast::*;
+
+pub mod parser;
+pub use parser::*;
+
+pub mod optimizer;
+pub use optimizer::*;
+
+pub mod resolver;
+pub use resolver::*;
+
+pub mod common_subexpressions;
+pub use common_subexpressions::*;
+
+pub mod copy_propagation;
+pub use copy_propagation::*;
+
+pub mod simplifier;
+pub use simplifier::*;
+
+pub mod dead_code;
+pub use dead_code::*;
+
+pub mod strength_reduction;
+pub use strength_reduction::*;
+
+pub mod cost;
+
+pub mod typechecker;
+
+pub mod passes;
+pub use passes::{
+    OptLevel, Optimization, Pass, PassManager, DEFAULT_MAX_ITERATIONS, DEFAULT_PASSES,
+};
+
+pub mod symbol_table;
+
+pub mod error;
+pub use error::{Binding, CompilerError, CompilerWarning, GenerationError};
+
+pub mod diagnostic;
+
+use clap::ValueEnum;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Points in the pipeline at which the AST can be dumped.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AstStage {
+    Parsed,
+    Folded,
+}
+
+/// What the source passed to [`compile`] and friends contains.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SourceFormat {
+    #[default]
+    Leo,
+    /// A JSON AST as written by [`ast::json::to_json`].
+    Json,
+}
+
+/// Settings for a single generation.
+#[derive(Clone)]
+pub struct Options {
+    pub dump_ast: Option<AstStage>,
+    pub max_nesting: usize,
+    pub source_format: SourceFormat,
+    /// The passes to run, in order, see [`OptLevel::passes`] for predefined lists.
+    pub passes: Vec<Pass>,
+    /// Whether [`eliminate_dead_code`] runs when it is in `passes`. Turn it off to keep every
+    /// binding.
+    pub eliminate_dead_code: bool,
+    /// Variables that count as outputs of the function, so their bindings are always kept.
+    pub keep: Vec<String>,
+    /// How many times the passes run before giving up on a fixed point.
+    pub max_iterations: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            dump_ast: None,
+            max_nesting: DEFAULT_NESTING_LIMIT,
+            source_format: SourceFormat::Leo,
+            passes: DEFAULT_PASSES.to_vec(),
+            eliminate_dead_code: true,
+            keep: Vec::new(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, GenerationError>;
+
+/// Checks a program and runs the default [`PassManager`] over it, which folds constants, binds
+/// repeated subexpressions once, replaces aliases with their source, simplifies algebraic
+/// identities and removes dead code until the program stops changing.
+/// Returns the optimized program and any warnings raised along the way.
+pub fn optimize(program: Program) -> Result<(Program, Vec<CompilerWarning>)> {
+    let optimization = optimize_with(program, &PassManager::default())?;
+    Ok((optimization.program, optimization.warnings))
+}
+
+/// Same as [`optimize`] with a custom pipeline, also reporting how many iterations it took.
+pub fn optimize_with(program: Program, passes: &PassManager) -> Result<Optimization> {
+    //reject references to undefined variables
+    resolve(&program)?;
+    //reject ill-typed expressions whether or not they fold
+    typechecker::check(&program)?;
+    Ok(passes.run(program)?)
+}
+
+/// Prints a program as Leo source.
+pub fn generate_code(program: &Program) -> String {
+    program.to_string()
+}
+
+/// The optimized program as Leo source, together with the warnings raised while compiling it.
+#[derive(Debug, PartialEq)]
+pub struct Compilation {
+    pub code: String,
+    pub warnings: Vec<CompilerWarning>,
+    /// How many times the passes ran before the program stopped changing.
+    pub iterations: usize,
+    /// The estimated number of circuit constraints of the optimized program, see [`cost`].
+    pub cost: u64,
+    /// The AST as versioned JSON at the stage [`Options::dump_ast`] names, if any.
+    pub ast_dump: Option<String>,
+}
+
+/// Compiles Leo source into optimized Leo source.
+pub fn compile(source: &str) -> Result<Compilation> {
+    compile_with_options(source, &Options::default())
+}
+
+/// Same as [`compile`] with non-default settings.
+pub fn compile_with_options(source: &str, options: &Options) -> Result<Compilation> {
+    build(source, options, |optimization, ast_dump| {
+        Ok(Compilation {
+            code: generate_code(&optimization.program),
+            warnings: optimization.warnings,
+            iterations: optimization.iterations,
+            cost: cost::program_cost(&optimization.program)?,
+            ast_dump,
+        })
+    })
+}
+
+/// Same as [`compile_with_options`], writing the optimized source to `writer` instead of
+/// returning it. Nothing is written unless compilation succeeds, and no AST is dumped.
+pub fn compile_to<W: Write>(
+    source: &str,
+    writer: &mut W,
+    options: &Options,
+) -> Result<Vec<CompilerWarning>> {
+    let options = Options {
+        dump_ast: None,
+        ..options.clone()
+    };
+    build(source, &options, |optimization, _| {
+        write!(writer, "{}", optimization.program)
+            .map_err(|e| GenerationError::WriteError(e.to_string()))?;
+        Ok(optimization.warnings)
+    })
+}
+
+//reads the source in the given format, optimizes it, dumping the AST along the way if requested,
+//and hands the result and the dump to `finish`. The passes, code generation and dropping the
+//program recurse once per nested block, so all of it runs on a stack with room for how deeply
+//the program nests.
+fn build<R>(
+    source: &str,
+    options: &Options,
+    finish: impl FnOnce(Optimization, Option<String>) -> Result<R>,
+) -> Result<R> {
+    let program = read(source, options)?;
+    ast::grow_stack_for_depth(program.nesting_depth(), || {
+        let mut ast_dump = None;
+        if options.dump_ast == Some(AstStage::Parsed) {
+            ast_dump = Some(ast::json::to_json(&program));
+        }
+        let mut passes = PassManager {
+            passes: options.passes.clone(),
+            max_iterations: options.max_iterations,
+            keep: options.keep.iter().map(|name| Symbol::new(name)).collect(),
+        };
+        if !options.eliminate_dead_code {
+            passes.passes.retain(|pass| *pass != Pass::DeadCode);
+        }
+        let optimization = optimize_with(program, &passes)?;
+        if options.dump_ast == Some(AstStage::Folded) {
+            ast_dump = Some(ast::json::to_json(&optimization.program));
+        }
+        finish(optimization, ast_dump)
+    })
+}
+
+//reads the source in the given format, rejecting programs that nest deeper than the limit
+fn read(source: &str, options: &Options) -> Result<Program> {
+    Ok(match options.source_format {
+        SourceFormat::Leo => parse_with_nesting_limit(source, options.max_nesting)?,
+        SourceFormat::Json => {
+            let program = ast::json::from_json(source).map_err(GenerationError::AstFormatError)?;
+            //the same limit the parser applies to source
+            if program.nesting_depth() > options.max_nesting {
+                return Err(GenerationError::AstFormatError(format!(
+                    "nesting exceeds the limit of {} levels",
+                    options.max_nesting
+                )));
+            }
+            program
+        }
+    })
+}
+
+/// Reads a Leo file, optimizes it and writes the result to `output`, returning any warnings raised.
+pub fn generate(input: PathBuf, output: PathBuf) -> Result<Vec<CompilerWarning>> {
+    generate_with_options(input, output, &Options::default())
+        .map(|compilation| compilation.warnings)
+}
+
+/// Same as [`generate`] with non-default settings, returning the whole [`Compilation`], which
+/// holds the AST dump if one was requested. Inputs ending in `.json` are read as a JSON AST
+/// instead of Leo source, whatever `options.source_format` says.
+pub fn generate_with_options(
+    input: PathBuf,
+    output: PathBuf,
+    options: &Options,
+) -> Result<Compilation> {
+    let source = fs::read_to_string(&input).map_err(|_| GenerationError::FileReadError)?;
+    let mut options = options.clone();
+    if input
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        options.source_format = SourceFormat::Json;
+    }
+    let compilation = compile_with_options(&source, &options)?;
+    fs::write(output, &compilation.code)
+        .map_err(|e| GenerationError::FileWriteError(e.to_string()))?;
+    Ok(compilation)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::json::to_json;
+    use crate::error::{Binding, CompilerError, CompilerWarning, GenerationError};
+    use crate::{compile, compile_to, compile_with_options, generate, Compilation, Options};
+    use crate::{parse, AstStage, OptLevel, Pass};
+    use crate::{Operator, Type};
+    use std::fs;
+    use std::io::{self, Write};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_basic() {
+        run_test("test_basic")
+    }
+
+    #[test]
+    fn test_revert() {
+        run_test("test_revert")
+    }
+
+    #[test]
+    fn test_ident() {
+        run_test("test_ident")
+    }
+
+    #[test]
+    fn test_boolean() {
+        run_test("test_boolean")
+    }
+
+    #[test]
+    fn test_if() {
+        run_test("test_if")
+    }
+
+    #[test]
+    fn test_scope() {
+        run_test("test_scope")
+    }
+
+    #[test]
+    fn test_field() {
+        run_test("test_field")
+    }
+
+    #[test]
+    fn test_literals() {
+        run_test("test_literals")
+    }
+
+    #[test]
+    fn test_dead_code() {
+        run_test("test_dead_code")
+    }
+
+    #[test]
+    fn test_copy_propagation() {
+        run_test("test_copy_propagation")
+    }
+
+    #[test]
+    fn test_cse() {
+        run_test("test_cse")
+    }
+
+    #[test]
+    fn test_strength_reduction() {
+        run_test("test_strength_reduction")
+    }
+
+    #[test]
+    fn test_json_input() {
+        run_test("test_json_input")
+    }
+
+    #[test]
+    fn test_order_of_operations() {
+        run_test("test_order_of_operations")
+    }
+
+    #[test]
+    fn test_compile_string() {
+        let source = "function main() {\n    let a = 1u8 + 2u8;\n    if a > 2u8 {\n        let b = a;\n    } else {\n        let b = 0u8;\n    }\n}";
+        assert_eq!(
+            compile(source),
+            Ok(Compilation {
+                code: String::from("function main() {\n    let a = 3u8;\n    let b = 3u8;\n}"),
+                warnings: vec![CompilerWarning::ConstantCondition(true)],
+                iterations: 2,
+                cost: 0,
+                ast_dump: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_compile_folds_simplified_values() {
+        let source = "function main(a: u8) {\n    let b = a * 0u8;\n    let c = b + 1u8;\n    let d = (a - 0u8) + c;\n}";
+        assert_eq!(
+            compile(source).map(|compilation| compilation.code),
+            Ok(String::from(
+                "function main(a: u8) {\n    let b = 0u8;\n    let c = 1u8;\n    let d = a + c;\n}"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_compile_keeps_bindings() {
+        let source =
+            "function main() {\n    let a = 1u8 + 1u8;\n    let b = a + a;\n    let c = a + b;\n}";
+        let keep_c = Options {
+            keep: vec![String::from("c")],
+            ..Options::default()
+        };
+        assert_eq!(
+            compile_with_options(source, &keep_c).map(|compilation| compilation.code),
+            Ok(String::from("function main() {\n    let c = 6u8;\n}"))
+        );
+        let source = "function main(x: u8) {\n    let a = 2u8;\n    let b = x;\n    return x;\n}";
+        let keep_all = Options {
+            eliminate_dead_code: false,
+            ..Options::default()
+        };
+        assert_eq!(
+            compile_with_options(source, &keep_all).map(|compilation| compilation.code),
+            Ok(String::from(source))
+        );
+    }
+
+    #[test]
+    fn test_compile_selected_passes() {
+        let source = "function main(x: u8) {\n    let a = 1u8 + 1u8;\n    let b = x - x;\n    return a + b;\n}";
+        let unoptimized = Options {
+            passes: OptLevel::O0.passes(),
+            ..Options::default()
+        };
+        assert_eq!(
+            compile_with_options(source, &unoptimized).map(|compilation| compilation.code),
+            Ok(String::from(source))
+        );
+        let folding = Options {
+            passes: vec![Pass::Fold],
+            ..Options::default()
+        };
+        assert_eq!(
+            compile_with_options(source, &folding).map(|compilation| compilation.code),
+            Ok(String::from(
+                "function main(x: u8) {\n    let a = 2u8;\n    let b = x - x;\n    return a + b;\n}"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_compile_dumps_ast() {
+        let source = "function main(x: u8) {\n    let a = 1u8 + 1u8;\n    return x + a;\n}";
+        let dump = |stage| {
+            let options = Options {
+                dump_ast: Some(stage),
+                ..Options::default()
+            };
+            compile_with_options(source, &options).map(|compilation| compilation.ast_dump)
+        };
+        //the folded AST is the one the optimized source parses to
+        let folded = parse(&compile(source).unwrap().code).unwrap();
+        assert_eq!(
+            dump(AstStage::Parsed),
+            Ok(Some(to_json(&parse(source).unwrap())))
+        );
+        assert_eq!(dump(AstStage::Folded), Ok(Some(to_json(&folded))));
+        assert_eq!(
+            compile(source).map(|compilation| compilation.ast_dump),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_compile_without_outputs() {
+        let source = fs::read_to_string("src/files/tests/test_ident.leo").unwrap();
+        let all = "function main() {\n    let a = 2u8;\n    let b = 4u8;\n    let c = 6u8;\n}";
+        assert_eq!(
+            compile(&source).map(|compilation| compilation.code),
+            Ok(String::from(all))
+        );
+        let keep_c = Options {
+            keep: vec![String::from("c")],
+            ..Options::default()
+        };
+        assert_eq!(
+            compile_with_options(&source, &keep_c).map(|compilation| compilation.code),
+            Ok(String::from("function main() {\n    let c = 6u8;\n}"))
+        );
+    }
+
+    #[test]
+    fn test_compile_keeps_grouping() {
+        //`(d * 2u8) + 3u8` and `(d - 1u8) - 1u8` at every level, with no operation folded into another
+        let source = "function main(d: u8) {\n    let c = d * 2u8 + 3u8;\n    let e = d - 1u8 - 1u8;\n    return c + e;\n}";
+        for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2] {
+            let options = Options {
+                passes: level.passes(),
+                ..Options::default()
+            };
+            assert_eq!(
+                compile_with_options(source, &options).map(|compilation| compilation.code),
+                Ok(String::from(source))
+            );
+        }
+        assert_eq!(
+            compile(source).map(|compilation| compilation.code),
+            Ok(String::from("function main(d: u8) {\n    let c = d + d + 3u8;\n    let e = d - 1u8 - 1u8;\n    return c + e;\n}"))
+        );
+    }
+
+    #[test]
+    fn test_compile_deep_nesting() {
+        //far deeper than the stack of a test thread allows without growing it
+        let options = Options {
+            max_nesting: 20_000,
+            ..Options::default()
+        };
+        let parentheses = format!(
+            "function main(a: u8) {{\n    return {}a + 1u8{};\n}}",
+            "(".repeat(10_000),
+            ")".repeat(10_000)
+        );
+        assert_eq!(
+            compile_with_options(&parentheses, &options).map(|compilation| compilation.code),
+            Ok(String::from(
+                "function main(a: u8) {\n    return (a + 1u8);\n}"
+            ))
+        );
+        let blocks = format!(
+            "function main(a: u8) {{\n{}    assert(a > 1u8);\n{}}}",
+            "if a > 1u8 {\n".repeat(1_000),
+            "} else {\n}\n".repeat(1_000)
+        );
+        let code = compile_with_options(&blocks, &options).unwrap().code;
+        assert_eq!(code.matches("if ").count(), 1_000);
+    }
+
+    //a writer whose every write fails
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_compile_to_reports_write_errors() {
+        let source = "function main() {\n    let a = 1u8;\n}";
+        let mut output = Vec::new();
+        assert_eq!(
+            compile_to(source, &mut output, &Options::default()),
+            Ok(Vec::new())
+        );
+        assert_eq!(output, b"function main() {\n    let a = 1u8;\n}");
+        assert_eq!(
+            compile_to(source, &mut Broken, &Options::default()),
+            Err(GenerationError::WriteError(String::from("disk full")))
+        );
+    }
+
+    #[test]
+    fn test_generate_reports_write_errors() {
+        let result = generate(
+            PathBuf::from("src/files/tests/test_basic.leo"),
+            PathBuf::from("src/files/actual/missing/test_basicActual.leo"),
+        );
+        let Err(GenerationError::FileWriteError(reason)) = result else {
+            panic!("expected a write error, found {:?}", result)
+        };
+        //the cause is kept, as for compile_to
+        let expected = fs::write("src/files/actual/missing/test_basicActual.leo", "").unwrap_err();
+        assert_eq!(reason, expected.to_string());
+    }
+
+    #[test]
+    fn test_undefined() {
+        test_failure(
+            "test_undefined",
+            CompilerError::UndefinedVariable {
+                name: String::from("d"),
+                similar: Some((
+                    String::from("b"),
+                    Binding::Local {
+                        statement: String::from("let b = 7u8 + 25u8;"),
+                    },
+                )),
+            },
+            3,
+        )
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        test_failure(
+            "test_type_mismatch",
+            CompilerError::TypeMismatch {
+                operator: Operator::Add,
+                left: Type::Bool,
+                right: Type::U8,
+            },
+            2,
+        )
+    }
+
+    #[test]
+    fn test_subtract_underflow() {
+        test_failure("test_sub_underflow", CompilerError::Underflow, 2)
+    }
+
+    #[test]
+    fn test_add_overflow() {
+        test_failure("test_add_overflow", CompilerError::Overflow, 2)
+    }
+
+    #[test]
+    fn test_div_zero() {
+        test_failure("test_div_zero", CompilerError::DivByZero, 2)
+    }
+
+    #[test]
+    fn test_field_div_zero() {
+        test_failure("test_field_div_zero", CompilerError::DivByZero, 3)
+    }
+
+    #[test]
+    fn test_assert_failure() {
+        test_failure("test_assert_failure", CompilerError::AssertionFailure, 3)
+    }
+
+    #[test]
+    fn test_mul_overflow() {
+        test_failure("test_mul_overflow", CompilerError::Overflow, 2)
+    }
+
+    //Writes a testfile to the /src/files/actual directory based on the filename
+    //which must exist in the /src/files/tests directory, either as Leo source or a JSON AST.
+    fn write_testfile(testname: &str) {
+        let mut read_from = format!("src/files/tests/{}.leo", testname);
+        if !PathBuf::from(&read_from).exists() {
+            read_from = format!("src/files/tests/{}.json", testname);
+        }
+        let write_to = format!("src/files/actual/{}Actual.leo", testname);
+        let result = generate(PathBuf::from(read_from), PathBuf::from(write_to));
+        if let Err(e) = result {
+            panic!("{}", e)
+        }
+    }
+
+    //attempts to compile a testfile and expects an error of provided type, raised in the
+    //statement that starts on the given line
+    fn test_failure(testname: &str, expected_error: CompilerError, line: usize) {
+        let read_from = format!("src/files/tests/{}.leo", testname);
+        let write_to = format!("src/files/actual/{}Actual.leo", testname);
+        let result = generate(PathBuf::from(read_from), PathBuf::from(write_to)).unwrap_err();
+        let GenerationError::CompilerError(error) = result else {
+            panic!("expected a compiler error, found {}", result)
+        };
+        assert_eq!(error.span().map(|span| span.start_line), Some(line));
+        assert_eq!(expected_error, error.without_span())
+    }
+
+    //compares the generated testfile to the exepcted based on the testname
+    fn compare_testfile(testname: &str) {
+        let path_to_actual = format!("src/files/actual/{}Actual.leo", testname);
+        let actual = fs::read_to_string(path_to_actual).expect("cannot read file");
+
+        let path_to_expected = format!("src/files/expected/{}Expected.leo", testname);
+        let expected = fs::read_to_string(path_to_expected).expect("cannot read file");
+
+        assert_eq!(actual, expected);
+    }
+
+    fn run_test(testname: &str) {
+        write_testfile(testname);
+        compare_testfile(testname);
+    }
+}
@@ -1,23 +1,12 @@
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
+// Command line interface over the library, see lib.rs for the compiler itself.
 
-pub mod ast;
-pub use ast::*;
+use coding_task_constant_folding::diagnostic::Diagnostic;
+use coding_task_constant_folding::*;
 
-pub mod parser;
-pub use parser::*;
-
-pub mod optimizer;
-pub use optimizer::*;
-
-mod error;
-use error::*;
-
-use clap::Parser as P;
+use clap::{Parser as P, ValueEnum};
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 // clap parsing struct for CLI commands
 #[derive(P)]
@@ -33,173 +22,175 @@ struct Cli {
     //output file path including name, requires input
     #[arg(short, long)]
     output: PathBuf,
+
+    //how errors and warnings are reported
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+
+    //print the AST as versioned JSON after the given stage
+    #[arg(long, value_enum)]
+    dump_ast: Option<AstStage>,
+
+    //how deeply parentheses and blocks may nest before the input is rejected
+    #[arg(long, default_value_t = DEFAULT_NESTING_LIMIT)]
+    max_nesting: usize,
+
+    //keep every binding instead of removing the ones no output reads
+    #[arg(
+        long,
+        help = "Keep every binding instead of removing the ones no return, assert or --keep reads"
+    )]
+    keep_bindings: bool,
+
+    //variables to treat as outputs, whose bindings are always kept
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Treat a variable as an output and keep its bindings. Functions without a return, assert or --keep are left unchanged"
+    )]
+    keep: Vec<String>,
+
+    //optimization level, from -O0 for no optimization to -O3 for every pass
+    #[arg(short = 'O', value_enum, default_value_t = OptLevel::default())]
+    opt_level: OptLevel,
+
+    //run exactly these passes in this order instead of those of the optimization level
+    #[arg(long, value_enum, value_delimiter = ',')]
+    passes: Option<Vec<Pass>>,
+
+    //leave a pass out of the pipeline, may be given more than once
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "PASS")]
+    disable_pass: Vec<Pass>,
+
+    //how many times the optimization passes run before giving up on a fixed point
+    #[arg(long, default_value_t = DEFAULT_MAX_ITERATIONS)]
+    max_iterations: usize,
+
+    //also print how many times the optimization passes ran, with human readable messages
+    #[arg(short, long)]
+    verbose: bool,
 }
 
-type Result<T> = std::result::Result<T, GenerationError>;
+//output formats for errors and warnings
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MessageFormat {
+    Human,
+    Json,
+}
 
-fn main() {
+//exits with a failure code when generation fails
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    let format = cli.message_format;
+    let verbose = cli.verbose && format == MessageFormat::Human;
+    let mut passes = cli.passes.unwrap_or_else(|| cli.opt_level.passes());
+    passes.retain(|pass| !cli.disable_pass.contains(pass));
+    let options = Options {
+        dump_ast: cli.dump_ast,
+        max_nesting: cli.max_nesting,
+        passes,
+        eliminate_dead_code: !cli.keep_bindings,
+        keep: cli.keep,
+        max_iterations: cli.max_iterations,
+        //picked from the input file's extension
+        ..Options::default()
+    };
     //check for cli options
     if cli.command == Some(String::from("generate")) {
         let input = cli.input;
         let output = cli.output;
-        let res = generate(input, output);
+        let res = generate_with_options(input.clone(), output.clone(), &options);
         match res {
             //success
-            Ok(_) => return,
+            Ok(compilation) => {
+                report(&compilation, &input, format, verbose);
+                ExitCode::SUCCESS
+            }
             //report error
-            Err(e) => println!("error generating file: {}", e),
+            Err(e) => {
+                report_error(&e, &input, &output, format);
+                ExitCode::FAILURE
+            }
         }
     } else {
         //replicating the behavior of fn write_testfile, but printing it out to the console
-        let read_from = format!("src/files/tests/test_basic.leo");
-        let write_to = format!("src/files/actual/test_basicActual.leo");
+        let read_from = PathBuf::from("src/files/tests/test_basic.leo");
+        let write_to = PathBuf::from("src/files/actual/test_basicActual.leo");
         //generate file
-        let result = generate(PathBuf::from(read_from), PathBuf::from(write_to));
+        let result = generate_with_options(read_from.clone(), write_to.clone(), &options);
         match result {
-            Ok(_) => {
+            Ok(compilation) => {
+                report(&compilation, &read_from, format, verbose);
                 //print to console
                 let file = fs::read_to_string("src/files/actual/test_basicActual.leo").unwrap();
-                println!("{}", file)
+                println!("{}", file);
+                ExitCode::SUCCESS
             }
             Err(e) => {
                 //report error
-                println!("error generating file: {}", e);
-            }
-        };
-    }
-}
-
-//code generation function which takes an input and output path
-pub fn generate(input: PathBuf, output: PathBuf) -> Result<()> {
-    match input.to_str() {
-        Some(path) => {
-            let unparsed_file =
-                fs::read_to_string(path).map_err(|_| GenerationError::FileReadError)?;
-            //parse file
-            let file = parse(&unparsed_file)?;
-            //perform constant folding
-            let first_pass = fold(file)?;
-            let optimized_file = fold(first_pass)?;
-
-            //report errors or write out to path
-            if let Some(out) = output.to_str() {
-                if let Ok(mut w) = fs::File::create(out) {
-                    write!(&mut w, "{}", optimized_file).unwrap();
-                    Ok(())
-                } else {
-                    Err(GenerationError::FileWriteError)
-                }
-            } else {
-                Err(GenerationError::FileWriteError)
+                report_error(&e, &read_from, &write_to, format);
+                ExitCode::FAILURE
             }
         }
-        None => {
-            return Err(GenerationError::FileReadError);
-        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::error::{CompilerError, GenerationError};
-    use crate::generate;
-    use std::fs;
-    use std::path::PathBuf;
-
-    #[test]
-    fn test_basic() {
-        run_test("test_basic")
-    }
-
-    #[test]
-    fn test_revert() {
-        run_test("test_revert")
+//prints the AST dump of a successful generation, if requested, its warnings and, if verbose,
+//how many iterations the passes needed
+fn report(compilation: &Compilation, input: &Path, format: MessageFormat, verbose: bool) {
+    if let Some(ast_dump) = &compilation.ast_dump {
+        println!("{}", ast_dump);
     }
-
-    #[test]
-    fn test_ident() {
-        run_test("test_ident")
-    }
-
-    #[test]
-    fn test_boolean() {
-        run_test("test_boolean")
-    }
-
-    #[test]
-    fn test_if() {
-        run_test("test_if")
-    }
-
-    #[test]
-    fn test_subtract_underflow() {
-        test_failure(
-            "test_sub_underflow",
-            GenerationError::CompilerError(CompilerError::Underflow),
-        )
-    }
-
-    #[test]
-    fn test_add_overflow() {
-        test_failure(
-            "test_add_overflow",
-            GenerationError::CompilerError(CompilerError::Overflow),
-        )
-    }
-
-    #[test]
-    fn test_div_zero() {
-        test_failure(
-            "test_div_zero",
-            GenerationError::CompilerError(CompilerError::DivByZero),
-        )
-    }
-
-    #[test]
-    fn test_mul_overflow() {
-        test_failure(
-            "test_mul_overflow",
-            GenerationError::CompilerError(CompilerError::Overflow),
-        )
+    report_warnings(&compilation.warnings, input, format);
+    if verbose {
+        eprintln!(
+            "note: the optimization passes ran {} time{}",
+            compilation.iterations,
+            if compilation.iterations == 1 { "" } else { "s" }
+        );
     }
+}
 
-    //Writes a testfile to the /src/files/actual directory based on the filename
-    //which must exist in the /src/files/tests directory.
-    fn write_testfile(testname: &str) {
-        let read_from = format!("src/files/tests/{}.leo", testname);
-        let write_to = format!("src/files/actual/{}Actual.leo", testname);
-        let result = generate(PathBuf::from(read_from), PathBuf::from(write_to));
-        match result {
-            Ok(_) => return,
-            Err(e) => {
-                println!("{}", e);
-                assert!(false)
+//prints warnings from a successful generation to stderr in the requested format
+fn report_warnings(warnings: &[CompilerWarning], input: &Path, format: MessageFormat) {
+    for warning in warnings {
+        match format {
+            MessageFormat::Human => eprintln!("warning: {}", warning),
+            MessageFormat::Json => {
+                eprintln!(
+                    "{}",
+                    Diagnostic::from_warning(warning, Some(input)).to_json()
+                )
             }
-        };
-    }
-
-    //attempts to compile a testfile and expects an error of provided type
-    fn test_failure(testname: &str, expected_error: GenerationError) {
-        let read_from = format!("src/files/tests/{}.leo", testname);
-        let write_to = format!("src/files/actual/{}Actual.leo", testname);
-        let result = generate(PathBuf::from(read_from), PathBuf::from(write_to)).unwrap_err();
-        assert_eq!(expected_error, result)
-    }
-
-    //compares the generated testfile to the exepcted based on the testname
-    fn compare_testfile(testname: &str) {
-        let path_to_actual = format!("src/files/actual/{}Actual.leo", testname);
-        let actual = fs::read_to_string(path_to_actual).expect("cannot read file");
-
-        let path_to_expected = format!("src/files/expected/{}Expected.leo", testname);
-        let expected = fs::read_to_string(path_to_expected).expect("cannot read file");
-
-        assert_eq!(actual, expected);
+        }
     }
+}
 
-    fn run_test(testname: &str) {
-        write_testfile(testname);
-        compare_testfile(testname);
+//prints a generation error to stderr in the requested format, blaming the output path for write errors
+fn report_error(error: &GenerationError, input: &Path, output: &Path, format: MessageFormat) {
+    match format {
+        MessageFormat::Human => {
+            eprintln!("error generating file: {}", error);
+            if let GenerationError::CompilerError(e) = error {
+                if let Some(span) = e.span() {
+                    eprintln!(
+                        "  --> {}:{}:{}",
+                        input.display(),
+                        span.start_line,
+                        span.start_column
+                    );
+                }
+            }
+            for note in error.notes() {
+                eprintln!("  note: {}", note);
+            }
+        }
+        MessageFormat::Json => {
+            let file = match error {
+                GenerationError::FileWriteError(_) => output,
+                _ => input,
+            };
+            eprintln!("{}", Diagnostic::from_error(error, Some(file)).to_json())
+        }
     }
 }
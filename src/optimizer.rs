@@ -1,26 +1,260 @@
 use crate::ast::*;
-use crate::error::CompilerError;
-use crate::Value::*;
+use crate::error::{CompilerError, CompilerWarning};
+use crate::symbol_table::SymbolTable;
 use crate::{Expression, Program};
-use std::collections::HashMap;
+use num_bigint::BigInt;
+use num_traits::Zero;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
 
 type Result<T> = std::result::Result<T, CompilerError>;
 
 //takes in a given Program AST and returns a new AST with constants, expressions, and booleans folded.
 pub fn fold(program: Program) -> Result<Program> {
-    //initialize memory
-    let mut memory = HashMap::new();
+    fold_with_warnings(program, &mut Vec::new())
+}
+
+//same as fold, but records warnings about the folded program in the provided list
+pub fn fold_with_warnings(
+    program: Program,
+    warnings: &mut Vec<CompilerWarning>,
+) -> Result<Program> {
+    let mut folder = ConstantFolder {
+        memory: SymbolTable::new(),
+        reassigned: Vec::new(),
+        warnings,
+        inline_branches: true,
+        names: FreshNames::default(),
+    };
+    folder.reconstruct_program(program)
+}
+
+//same as fold_with_warnings, but if statements with a known condition are kept with only the
+//branch that runs folded, leaving them for eliminate_branches
+pub fn fold_keeping_branches(
+    program: Program,
+    warnings: &mut Vec<CompilerWarning>,
+) -> Result<Program> {
+    let mut folder = ConstantFolder {
+        memory: SymbolTable::new(),
+        reassigned: Vec::new(),
+        warnings,
+        inline_branches: false,
+        names: FreshNames::default(),
+    };
+    folder.reconstruct_program(program)
+}
+
+//inlines the branch that runs of every if statement whose condition is `true` or `false`,
+//renaming its bindings that would clash with other bindings of the same names
+pub fn eliminate_branches(program: Program, warnings: &mut Vec<CompilerWarning>) -> Program {
+    let mut eliminator = BranchEliminator {
+        visible: SymbolTable::new(),
+        names: FreshNames::default(),
+        warnings,
+    };
+    let Ok(program) = eliminator.reconstruct_program(program);
+    program
+}
+
+// Reconstructor that folds constant expressions and inlines if statements with a known condition.
+// Memory holds every declared name with its value, or None when the value is unknown.
+// The tree is moved through the folder, so only the nodes that actually fold are rebuilt.
+//
+// Reassignments are logged with the value they replaced. Each branch of an if with an
+// unknown condition is folded in turn and then undone through the log, and afterwards a
+// name keeps a value only if both branches leave it with the same one.
+struct ConstantFolder<'a> {
+    memory: SymbolTable<Option<Value>>,
+    reassigned: Vec<(Symbol, Option<Value>)>,
+    warnings: &'a mut Vec<CompilerWarning>,
+    //whether ifs with a known condition are replaced by the branch that runs
+    inline_branches: bool,
+    //every name in the program, which fresh names for clashing inlined bindings avoid
+    names: FreshNames,
+}
+
+impl ConstantFolder<'_> {
+    //changes the value of a visible variable, logging the value it replaces
+    fn reassign(&mut self, variable: Symbol, value: Option<Value>) {
+        if let Some(slot) = self.memory.lookup_mut(&variable) {
+            let previous = std::mem::replace(slot, value);
+            self.reassigned.push((variable, previous));
+        }
+    }
+
+    //undoes the reassignments logged since `mark`, returning the values they left behind
+    fn undo_reassignments(&mut self, mark: usize) -> HashMap<Symbol, Option<Value>> {
+        let mut after = HashMap::new();
+        while self.reassigned.len() > mark {
+            let (variable, previous) = self.reassigned.pop().unwrap();
+            //names bound inside the branch are already gone
+            if let Some(slot) = self.memory.lookup_mut(&variable) {
+                //the log is undone newest first, so the first value seen is the final one
+                let current = std::mem::replace(slot, previous);
+                after.entry(variable).or_insert(current);
+            }
+        }
+        after
+    }
+
+    //keeps the values both branches agree on, where a branch that did not reassign a
+    //variable leaves it with its value before the if
+    fn join(
+        &mut self,
+        after_a: HashMap<Symbol, Option<Value>>,
+        mut after_b: HashMap<Symbol, Option<Value>>,
+    ) {
+        let before =
+            |folder: &Self, variable: &Symbol| folder.memory.lookup(variable).cloned().flatten();
+        let mut joined = Vec::new();
+        for (variable, a) in after_a {
+            let b = after_b
+                .remove(&variable)
+                .unwrap_or_else(|| before(self, &variable));
+            joined.push((variable, meet(a, b)));
+        }
+        for (variable, b) in after_b {
+            joined.push((variable.clone(), meet(before(self, &variable), b)));
+        }
+        for (variable, value) in joined {
+            self.reassign(variable, value);
+        }
+    }
+
+    //folds an if statement, later holds the names bound after it in the same scope
+    fn fold_if(
+        &mut self,
+        expression: Expression,
+        statements_a: Vec<Statement>,
+        statements_b: Vec<Statement>,
+        span: SourceSpan,
+        later: &DeclaredNames,
+    ) -> Result<Vec<Statement>> {
+        // evaluate expression, if yes insert A if no insert B if none just continue
+        let written_as_literal =
+            matches!(&expression, Expression::Value(value) if matches!(**value, Value::Boolean(_)));
+        let expression = evaluate(expression, &self.memory)?;
+        let x = match &expression {
+            Expression::Value(value) if matches!(**value, Value::Boolean(_)) => {
+                **value == Value::Boolean(true)
+            }
+            //keep the if with the condition folded as far as it goes, and fold both branches on
+            //their own since either may run
+            _ => {
+                let mark = self.reassigned.len();
+                let statements_a = self.reconstruct_branch(statements_a)?;
+                let after_a = self.undo_reassignments(mark);
+                let statements_b = self.reconstruct_branch(statements_b)?;
+                let after_b = self.undo_reassignments(mark);
+                self.join(after_a, after_b);
+                return Ok(vec![Statement::If {
+                    expression,
+                    statements_a,
+                    statements_b,
+                    span,
+                }]);
+            }
+        };
+
+        if !self.inline_branches {
+            //reported like an inlined branch, except for a literal condition, which a kept if from
+            //an earlier run has as well
+            if !written_as_literal {
+                self.warnings.push(CompilerWarning::ConstantCondition(x));
+            }
+            //the other branch never runs, and is left as written
+            let (taken, other) = if x {
+                (statements_a, statements_b)
+            } else {
+                (statements_b, statements_a)
+            };
+            let taken = self.reconstruct_branch(taken)?;
+            let (statements_a, statements_b) = if x { (taken, other) } else { (other, taken) };
+            return Ok(vec![Statement::If {
+                expression,
+                statements_a,
+                statements_b,
+                span,
+            }]);
+        }
+
+        //fold the winning code block from if expression in its own scope
+        let mark = self.reassigned.len();
+        self.memory.enter_scope();
+        let branch = self.reconstruct_statements(if x { statements_a } else { statements_b });
+        let scope = self.memory.exit_scope();
+        let branch = branch?;
+
+        //inlining moves the branch bindings into this scope, where those that would clash with
+        //other bindings of the same names get fresh names
+        let renames = self.names.renames(
+            scope
+                .keys()
+                .filter(|name| self.memory.lookup(name).is_some() || later.contains(name)),
+        );
+        for (name, value) in scope {
+            let name = renames.get(&name).cloned().unwrap_or(name);
+            self.memory.bind(name, value)?;
+        }
+        for (variable, _) in &mut self.reassigned[mark..] {
+            if let Some(fresh) = renames.get(variable) {
+                *variable = fresh.clone();
+            }
+        }
+
+        //append the winning code block
+        self.warnings.push(CompilerWarning::ConstantCondition(x));
+        Ok(rename(branch, &renames))
+    }
+}
 
-    //grab name and inputs
-    let name = program.name;
-    let inputs = program.inputs;
+impl Reconstructor for ConstantFolder<'_> {
+    type Error = CompilerError;
+
+    fn reconstruct_program(&mut self, program: Program) -> Result<Program> {
+        //inputs are declared but their values are unknown
+        for input in &program.inputs {
+            self.memory.bind(input.name.clone(), None)?;
+        }
+        self.names = FreshNames::of(&program);
+        rebuild_program(self, program)
+    }
 
-    //iterate through statements and attempt evaluation
-    let mut statements = program.statements;
+    fn reconstruct_statements(&mut self, statements: Vec<Statement>) -> Result<Vec<Statement>> {
+        //ifs need to know the names bound after them, counted once up front and
+        //forgotten again statement by statement to stay linear in the program size
+        let mut later = DeclaredNames::default();
+        walk_statements(&mut later, &statements);
 
-    //construct a new list during iteration
-    let mut new_statements = Vec::new();
-    for statement in statements.iter_mut() {
+        //construct a new list during iteration
+        let mut new_statements = Vec::with_capacity(statements.len());
+        for statement in statements {
+            later.forget(&statement);
+            let span = statement.span();
+            let folded = match statement {
+                Statement::If {
+                    expression,
+                    statements_a,
+                    statements_b,
+                    ..
+                } => self.fold_if(expression, statements_a, statements_b, span, &later),
+                statement => self.reconstruct_statement(statement),
+            };
+            new_statements.extend(folded.map_err(|error| error.in_statement(span))?);
+        }
+        Ok(new_statements)
+    }
+
+    fn reconstruct_branch(&mut self, statements: Vec<Statement>) -> Result<Vec<Statement>> {
+        //the branch sees the values known before the if, and its own bindings end with it
+        self.memory.enter_scope();
+        let statements = self.reconstruct_statements(statements);
+        self.memory.exit_scope();
+        statements
+    }
+
+    fn reconstruct_statement(&mut self, statement: Statement) -> Result<Vec<Statement>> {
         match statement {
             //assign statement "let a = 1u8 + 2u8"
             Statement::Assign {
@@ -28,253 +262,824 @@ pub fn fold(program: Program) -> Result<Program> {
                 variable,
                 //1u8 + 2u8
                 expression,
+                span,
             } => {
-                //recursive evaluation of the expression tree, with memory provided. memory is not mutated by evaluate fn.
-                let opt = evaluate(expression.clone(), &mut memory);
-                match opt {
-                    Some(eval_result) => match eval_result {
-                        //fold current expression if Ok result
-                        Ok(val) => {
-                            //*expression = Expression::Value(Box::new(val.clone()));
-                            memory.insert(variable.clone(), val.clone());
-                            new_statements.push(Statement::Assign {
-                                variable: variable.clone(),
-                                expression: Expression::Value(Box::new(val.clone())),
-                            })
-                        }
-                        //return given error from evaluation
-                        Err(e) => return Err(e),
-                    },
-                    //cannot be folded due to unkown identifier, move on
-                    None => new_statements.push(statement.clone()),
+                let expression = self.reconstruct_expression(expression)?;
+                //remember the value if the expression folded to a constant
+                self.memory.bind(variable.clone(), constant(&expression))?;
+                Ok(vec![Statement::Assign {
+                    variable,
+                    expression,
+                    span,
+                }])
+            }
+            //reassign statement "a = a + 1u8"
+            Statement::Reassign {
+                variable,
+                expression,
+                span,
+            } => {
+                let expression = self.reconstruct_expression(expression)?;
+                self.reassign(variable.clone(), constant(&expression));
+                Ok(vec![Statement::Reassign {
+                    variable,
+                    expression,
+                    span,
+                }])
+            }
+            //assert statement "assert(a > 1u8)", which is reported if it can never hold
+            Statement::Assert { expression, span } => {
+                let expression = self.reconstruct_expression(expression)?;
+                if constant(&expression) == Some(Value::Boolean(false)) {
+                    return Err(CompilerError::AssertionFailure);
                 }
+                Ok(vec![Statement::Assert { expression, span }])
             }
+            Statement::Return { expression, span } => Ok(vec![Statement::Return {
+                expression: self.reconstruct_expression(expression)?,
+                span,
+            }]),
             Statement::If {
                 expression,
                 statements_a,
                 statements_b,
-            } => {
-                // evaluate expression, if yes insert A if no insert B if none just continue
-                let opt = evaluate(expression.clone(), &mut memory);
-                match opt {
-                    Some(result) => match result {
-                        // If there is a boolean returned
-                        Ok(boolean) => {
-                            if let Boolean(x) = boolean {
-                                //append the winning code block from if expression 
-                                if x {
-                                    new_statements.append(statements_a);
-                                } else {
-                                    new_statements.append(statements_b);
-                                }
-                            } else {
-                                //move on
-                                new_statements.push(statement.clone());
-                            }
-                        }
-                        Err(e) => return Err(e),
-                    },
-                    //move on
-                    None => new_statements.push(statement.clone()),
-                }
-            }
+                span,
+            } => self.fold_if(
+                expression,
+                statements_a,
+                statements_b,
+                span,
+                &DeclaredNames::default(),
+            ),
         }
     }
-    Ok(Program {
-        name,
-        inputs,
-        statements: new_statements,
-    })
+
+    fn reconstruct_expression(&mut self, expression: Expression) -> Result<Expression> {
+        //evaluation of the expression tree as far as the values in memory allow
+        evaluate(expression, &self.memory)
+    }
+}
+
+//the value of an expression that folded to a constant
+fn constant(expression: &Expression) -> Option<Value> {
+    match expression {
+        Expression::Value(value)
+            if matches!(
+                **value,
+                Value::Integer(_)
+                    | Value::Field(_)
+                    | Value::Scalar(_)
+                    | Value::Address(_)
+                    | Value::Boolean(_)
+            ) =>
+        {
+            Some((**value).clone())
+        }
+        _ => None,
+    }
 }
 
-//evaluates expressions of various kinds detecting errors
-fn evaluate(exp: Expression, memory: &mut HashMap<String, Value>) -> Option<Result<Value>> {
-    match exp {
-        Expression::Binary {
-            left,
-            operator,
-            right,
-        } => {
-            let lv;
-            let lv_valid = evaluate(Expression::Value(Box::new(left)), memory);
-            match lv_valid {
-                Some(lv_result) => match lv_result {
-                    //fold right value
-                    Ok(val) => {
-                        lv = val;
+//what is known about a variable after two paths that meet, its value if both agree on it
+fn meet(a: Option<Value>, b: Option<Value>) -> Option<Value> {
+    if a == b {
+        a
+    } else {
+        None
+    }
+}
+
+// Reconstructor that inlines ifs with a literal condition, the same way the constant folder
+// does for conditions it folds. Visible holds every visible name.
+struct BranchEliminator<'a> {
+    visible: SymbolTable<()>,
+    names: FreshNames,
+    warnings: &'a mut Vec<CompilerWarning>,
+}
+
+impl Reconstructor for BranchEliminator<'_> {
+    type Error = Infallible;
+
+    fn reconstruct_program(
+        &mut self,
+        program: Program,
+    ) -> std::result::Result<Program, Infallible> {
+        for input in &program.inputs {
+            let _ = self.visible.declare(input.name.clone(), ());
+        }
+        self.names = FreshNames::of(&program);
+        rebuild_program(self, program)
+    }
+
+    fn reconstruct_statements(
+        &mut self,
+        statements: Vec<Statement>,
+    ) -> std::result::Result<Vec<Statement>, Infallible> {
+        let mut later = DeclaredNames::default();
+        walk_statements(&mut later, &statements);
+
+        let mut new_statements = Vec::with_capacity(statements.len());
+        for statement in statements {
+            later.forget(&statement);
+            match statement {
+                Statement::If {
+                    expression,
+                    statements_a,
+                    statements_b,
+                    ..
+                } if matches!(&expression, Expression::Value(value) if matches!(**value, Value::Boolean(_))) =>
+                {
+                    let x = constant(&expression) == Some(Value::Boolean(true));
+                    self.visible.enter_scope();
+                    let Ok(branch) =
+                        self.reconstruct_statements(if x { statements_a } else { statements_b });
+                    let scope = self.visible.exit_scope();
+                    let renames = self.names.renames(scope.keys().filter(|name| {
+                        self.visible.lookup(name).is_some() || later.contains(name)
+                    }));
+                    self.warnings.push(CompilerWarning::ConstantCondition(x));
+                    for name in scope.into_keys() {
+                        let name = renames.get(&name).cloned().unwrap_or(name);
+                        let _ = self.visible.declare(name, ());
                     }
-                    //return evaluation error back to caller
-                    Err(e) => return Some(Err(e)),
-                },
-                //could not fold, move on
-                None => return None,
-            }
-            let rv;
-            //attempt evaluation of right side
-            let rv_valid = evaluate(*right, memory);
-            match rv_valid {
-                Some(rv_result) => match rv_result {
-                    //fold right value
-                    Ok(val) => {
-                        rv = val;
+                    new_statements.extend(rename(branch, &renames));
+                }
+                statement => {
+                    if let Statement::Assign { variable, .. } = &statement {
+                        let _ = self.visible.declare(variable.clone(), ());
                     }
-                    //return evaluation error back to caller
-                    Err(e) => return Some(Err(e)),
-                },
-                //could not fold, move on
-                None => return None,
+                    new_statements.extend(self.reconstruct_statement(statement)?);
+                }
             }
+        }
+        Ok(new_statements)
+    }
+
+    fn reconstruct_branch(
+        &mut self,
+        statements: Vec<Statement>,
+    ) -> std::result::Result<Vec<Statement>, Infallible> {
+        self.visible.enter_scope();
+        let statements = self.reconstruct_statements(statements);
+        self.visible.exit_scope();
+        statements
+    }
 
-            //evaluation
-            match operator {
-                Operator::Add => Some(add_u8(lv, rv)),
-                Operator::Subtract => Some(sub_u8(lv, rv)),
-                Operator::Multiply => Some(mul_u8(lv, rv)),
-                Operator::Divide => Some(div_u8(lv, rv)),
-                Operator::GreaterThan => Some(gt_bool(lv, rv)),
-                Operator::LessThan => Some(lt_bool(lv, rv)),
-                Operator::Equal => Some(eq_bool(lv, rv)),
+    //only statements change
+    fn reconstruct_expression(
+        &mut self,
+        expression: Expression,
+    ) -> std::result::Result<Expression, Infallible> {
+        Ok(expression)
+    }
+}
+
+//every name of a program, which fresh names for inlined bindings that would clash must avoid
+#[derive(Default)]
+struct FreshNames(HashSet<Symbol>);
+
+impl FreshNames {
+    fn of(program: &Program) -> Self {
+        let mut names = FreshNames::default();
+        names.visit_program(program);
+        names
+    }
+
+    //picks a fresh name for each of the given names, `a_1` for `a` unless that is taken
+    fn renames<'a>(
+        &mut self,
+        clashing: impl Iterator<Item = &'a Symbol>,
+    ) -> HashMap<Symbol, Symbol> {
+        let mut renames = HashMap::new();
+        for name in clashing {
+            let fresh = (1..)
+                .map(|n| Symbol::new(&format!("{}_{}", name, n)))
+                .find(|fresh| !self.0.contains(fresh))
+                .unwrap();
+            self.0.insert(fresh.clone());
+            renames.insert(name.clone(), fresh);
+        }
+        renames
+    }
+}
+
+impl Visitor for FreshNames {
+    fn visit_input(&mut self, input: &Input) {
+        self.0.insert(input.name.clone());
+    }
+
+    //every name that is used is also bound
+    fn visit_expression(&mut self, _expression: &Expression) {}
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let Statement::Assign { variable, .. } = statement {
+            self.0.insert(variable.clone());
+        }
+        walk_statement(self, statement)
+    }
+}
+
+//replaces the renamed names in inlined statements, which only refer to the bindings of the branch
+fn rename(statements: Vec<Statement>, renames: &HashMap<Symbol, Symbol>) -> Vec<Statement> {
+    if renames.is_empty() {
+        return statements;
+    }
+    let Ok(statements) = Rename(renames).reconstruct_statements(statements);
+    statements
+}
+
+// Reconstructor that renames bindings and their uses.
+struct Rename<'a>(&'a HashMap<Symbol, Symbol>);
+
+impl Rename<'_> {
+    fn name(&self, name: Symbol) -> Symbol {
+        self.0.get(&name).cloned().unwrap_or(name)
+    }
+}
+
+impl Reconstructor for Rename<'_> {
+    type Error = Infallible;
+
+    fn reconstruct_statement(
+        &mut self,
+        statement: Statement,
+    ) -> std::result::Result<Vec<Statement>, Infallible> {
+        let mut statements = rebuild_statement(self, statement)?;
+        for statement in &mut statements {
+            if let Statement::Assign { variable, .. } | Statement::Reassign { variable, .. } =
+                statement
+            {
+                *variable = self.name(variable.clone());
             }
         }
-        //hit the end of the expression
-        Expression::Value(x) => match *x {
-            Integer(_) => Some(Ok(*x)),
-            //check if iden has been seen before
-            Identifier(iden) => {
-                let poll = memory.get(&iden);
-                match poll {
-                    Some(val) => Some(Ok(val.clone())),
-                    None => return None,
+        Ok(statements)
+    }
+
+    fn reconstruct_value(&mut self, value: Value) -> std::result::Result<Value, Infallible> {
+        match value {
+            Value::Identifier(name) => Ok(Value::Identifier(self.name(name))),
+            value => rebuild_value(self, value),
+        }
+    }
+}
+
+//counts the bindings of every name made by the visited statements, including inside nested branches
+#[derive(Default)]
+struct DeclaredNames(HashMap<Symbol, usize>);
+
+impl DeclaredNames {
+    fn contains(&self, name: &Symbol) -> bool {
+        self.0.contains_key(name)
+    }
+
+    //drops the bindings made by a statement that was already counted
+    fn forget(&mut self, statement: &Statement) {
+        let mut declared = DeclaredNames::default();
+        declared.visit_statement(statement);
+        for (name, count) in declared.0 {
+            if let Some(remaining) = self.0.get_mut(&name) {
+                *remaining -= count;
+                if *remaining == 0 {
+                    self.0.remove(&name);
                 }
             }
-            Value::Expression(_) => None,
-            Boolean(_) => Some(Ok(*x)),
-        },
+        }
     }
 }
 
-//helper function to attempt addition and handle errors
-fn add_u8(v1: Value, v2: Value) -> Result<Value> {
-    match v1 {
-        Integer(x) => match v2 {
-            Integer(y) => {
-                let (val, overflow) = x.overflowing_add(y);
-                if overflow {
-                    Err(CompilerError::Overflow)
-                } else {
-                    Ok(Integer(val))
-                }
+impl Visitor for DeclaredNames {
+    //bindings are only made by statements
+    fn visit_expression(&mut self, _expression: &Expression) {}
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let Statement::Assign { variable, .. } = statement {
+            *self.0.entry(variable.clone()).or_default() += 1;
+        }
+        walk_statement(self, statement)
+    }
+}
+
+//an expression or one of its operands after folding
+enum Folded {
+    //a literal, or the result of operations on known values
+    Constant(Value),
+    //an identifier with a known value, which stays as written unless an operation folds it away
+    Known { written: Value, value: Value },
+    //anything that could not be folded, rebuilt around the parts that did
+    Unknown(Expression),
+}
+
+impl Folded {
+    fn is_known(&self) -> bool {
+        !matches!(self, Folded::Unknown(_))
+    }
+
+    //the value of a known constant, to be folded further
+    fn into_value(self) -> Value {
+        match self {
+            Folded::Constant(value) | Folded::Known { value, .. } => value,
+            Folded::Unknown(_) => unreachable!("only known values are folded"),
+        }
+    }
+
+    //the folded value as it is written back into the tree
+    fn into_expression(self) -> Expression {
+        match self {
+            Folded::Constant(value) | Folded::Known { written: value, .. } => {
+                Expression::Value(Box::new(value))
             }
-            _ => return Err(CompilerError::MismatchType),
-        },
-        _ => return Err(CompilerError::MismatchType),
+            Folded::Unknown(expression) => expression,
+        }
     }
 }
 
-//helper function to attempt subtraction and handle errors
-fn sub_u8(v1: Value, v2: Value) -> Result<Value> {
-    match v1 {
-        Integer(x) => match v2 {
-            Integer(y) => {
-                let (val, overflow) = x.overflowing_sub(y);
-                if overflow {
-                    Err(CompilerError::Underflow)
-                } else {
-                    Ok(Integer(val))
-                }
+//evaluates an expression as far as memory allows, detecting errors. Every operation on known
+//values is folded and the rest of the expression is rebuilt around the results, so
+//`5u8 + d * (2u8 + 3u8)` becomes `5u8 + d * 5u8`. Expressions that fold completely become the value.
+fn evaluate(exp: Expression, memory: &SymbolTable<Option<Value>>) -> Result<Expression> {
+    Ok(match evaluate_expression(exp, memory)? {
+        Folded::Unknown(expression) => expression,
+        folded => Expression::Value(Box::new(folded.into_value())),
+    })
+}
+
+fn evaluate_expression(exp: Expression, memory: &SymbolTable<Option<Value>>) -> Result<Folded> {
+    grow_stack(|| match exp.into_parts() {
+        //fold both operands first, then the operation if both are known
+        Parts::Binary(left, operator, right) => {
+            let lv = evaluate_expression(left, memory)?;
+            let rv = evaluate_expression(right, memory)?;
+            if lv.is_known() && rv.is_known() {
+                Ok(Folded::Constant(apply(
+                    &operator,
+                    lv.into_value(),
+                    rv.into_value(),
+                )?))
+            } else {
+                //an unknown side keeps the operation, with both sides folded as far as they go
+                Ok(Folded::Unknown(Expression::binary(
+                    lv.into_expression(),
+                    operator,
+                    rv.into_expression(),
+                )))
+            }
+        }
+        Parts::Value(value) => evaluate_value(value, memory),
+    })
+}
+
+fn evaluate_value(value: Value, memory: &SymbolTable<Option<Value>>) -> Result<Folded> {
+    Ok(match value {
+        Value::Integer(_)
+        | Value::Field(_)
+        | Value::Scalar(_)
+        | Value::Address(_)
+        | Value::Boolean(_) => Folded::Constant(value),
+        //group operations are curve arithmetic, which is left to run time
+        Value::Group(_) => Folded::Unknown(Expression::Value(Box::new(value))),
+        //check if iden has been seen before and has a known value
+        Value::Identifier(ref iden) => match memory.lookup(iden) {
+            Some(Some(known)) => Folded::Known {
+                written: value,
+                value: known.clone(),
+            },
+            _ => Folded::Unknown(Expression::Value(Box::new(value))),
+        },
+        //parentheses are evaluated like the rest of the tree, and dropped once they hold a single value
+        Value::Expression(inner) => match evaluate_expression(*inner, memory)? {
+            Folded::Unknown(inner) if matches!(inner, Expression::Value(_)) => {
+                Folded::Unknown(inner)
             }
-            _ => return Err(CompilerError::MismatchType),
+            Folded::Unknown(inner) => Folded::Unknown(Expression::Value(Box::new(
+                Value::Expression(Box::new(inner)),
+            ))),
+            folded => Folded::Constant(folded.into_value()),
         },
-        _ => return Err(CompilerError::MismatchType),
+    })
+}
+
+//evaluation of a single operator on two known values
+fn apply(operator: &Operator, lv: Value, rv: Value) -> Result<Value> {
+    match operator {
+        Operator::Add | Operator::Subtract | Operator::Multiply | Operator::Divide => {
+            arithmetic(operator, lv, rv)
+        }
+        Operator::GreaterThan => compare(lv, rv, |x, y| x > y),
+        Operator::LessThan => compare(lv, rv, |x, y| x < y),
+        Operator::Equal => eq_bool(lv, rv),
     }
 }
 
-//helper function to attempt multiplication and handle errors
-fn mul_u8(v1: Value, v2: Value) -> Result<Value> {
-    match v1 {
-        Integer(x) => match v2 {
-            Integer(y) => {
-                let (val, overflow) = x.overflowing_mul(y);
-                if overflow {
-                    Err(CompilerError::Overflow)
-                } else {
-                    Ok(Integer(val))
-                }
+//helper function to attempt arithmetic on two integers of the same type, two fields or two
+//scalars. Integer operations are done exactly and the result is then checked against the range
+//of the type, while field and scalar operations wrap around their modulus.
+fn arithmetic(operator: &Operator, v1: Value, v2: Value) -> Result<Value> {
+    match (v1, v2) {
+        (Value::Integer(x), Value::Integer(y)) if x.integer_type == y.integer_type => {
+            let integer_type = x.integer_type;
+            let value = match operator {
+                Operator::Add => x.value + y.value,
+                Operator::Subtract => x.value - y.value,
+                Operator::Multiply => x.value * y.value,
+                Operator::Divide => divide(x.value, y.value)?,
+                _ => return Err(CompilerError::MismatchType),
+            };
+            if value > integer_type.max() {
+                Err(CompilerError::Overflow)
+            } else if value < integer_type.min() {
+                Err(CompilerError::Underflow)
+            } else {
+                Ok(Value::Integer(Integer {
+                    integer_type,
+                    value,
+                }))
             }
-            _ => return Err(CompilerError::MismatchType),
-        },
-        _ => return Err(CompilerError::MismatchType),
+        }
+        (Value::Field(x), Value::Field(y)) => modular(operator, &x, &y).map(Value::Field),
+        (Value::Scalar(x), Value::Scalar(y)) => modular(operator, &x, &y).map(Value::Scalar),
+        _ => Err(CompilerError::MismatchType),
+    }
+}
+
+//helper function to attempt arithmetic on two elements of a prime field
+fn modular<T: PrimeField>(operator: &Operator, x: &T, y: &T) -> Result<T> {
+    match operator {
+        Operator::Add => Ok(x.add(y)),
+        Operator::Subtract => Ok(x.sub(y)),
+        Operator::Multiply => Ok(x.mul(y)),
+        Operator::Divide => x.div(y).ok_or(CompilerError::DivByZero),
+        _ => Err(CompilerError::MismatchType),
     }
 }
 
 //helper function to attempt division and handle errors
-fn div_u8(v1: Value, v2: Value) -> Result<Value> {
-    match v1 {
-        Integer(x) => match v2 {
-            Integer(y) => {
-                if y == 0 {
-                    return Err(CompilerError::DivByZero);
-                }
-                let (val, overflow) = x.overflowing_div(y);
-                if overflow {
-                    return Err(CompilerError::Overflow);
-                } else {
-                    return Ok(Integer(val));
-                }
-            }
-            _ => return Err(CompilerError::MismatchType),
-        },
-        _ => return Err(CompilerError::MismatchType),
+fn divide(x: BigInt, y: BigInt) -> Result<BigInt> {
+    if y.is_zero() {
+        return Err(CompilerError::DivByZero);
     }
+    //truncates towards zero like Leo
+    Ok(x / y)
 }
 
-//helper function to attempt gt and handle errors
-fn gt_bool(v1: Value, v2: Value) -> Result<Value> {
-    match v1 {
-        Integer(x) => match v2 {
-            Integer(y) => {
-                if x > y {
-                    Ok(Boolean(true))
-                } else {
-                    Ok(Boolean(false))
-                }
-            }
-            _ => return Err(CompilerError::MismatchType),
-        },
-        _ => return Err(CompilerError::MismatchType),
+//helper function to attempt < or > on two integers of the same type
+fn compare(v1: Value, v2: Value, comparison: fn(&BigInt, &BigInt) -> bool) -> Result<Value> {
+    match (v1, v2) {
+        (Value::Integer(x), Value::Integer(y)) if x.integer_type == y.integer_type => {
+            Ok(Value::Boolean(comparison(&x.value, &y.value)))
+        }
+        _ => Err(CompilerError::MismatchType),
+    }
+}
+
+//helper function to attempt == on integers, fields, scalars, addresses or booleans and handle errors
+fn eq_bool(v1: Value, v2: Value) -> Result<Value> {
+    match (v1, v2) {
+        (Value::Integer(x), Value::Integer(y)) if x.integer_type == y.integer_type => {
+            Ok(Value::Boolean(x.value == y.value))
+        }
+        (Value::Field(x), Value::Field(y)) => Ok(Value::Boolean(x == y)),
+        (Value::Scalar(x), Value::Scalar(y)) => Ok(Value::Boolean(x == y)),
+        (Value::Address(x), Value::Address(y)) => Ok(Value::Boolean(x == y)),
+        (Value::Boolean(x), Value::Boolean(y)) => Ok(Value::Boolean(x == y)),
+        _ => Err(CompilerError::MismatchType),
     }
 }
 
-//helper function to attempt lt and handle errors
-fn lt_bool(v1: Value, v2: Value) -> Result<Value> {
-    match v1 {
-        Integer(x) => match v2 {
-            Integer(y) => {
-                if x < y {
-                    Ok(Boolean(true))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leo;
+
+    #[test]
+    fn test_fold_constants_and_known_identifiers() {
+        let program = leo!(function main(a: u8) {
+            let b = 1u8 + 2u8;
+            let c = b * 2u8;
+            let d = a + c;
+        });
+        let expected = leo!(function main(a: u8) {
+            let b = 3u8;
+            let c = 6u8;
+            let d = a + c;
+        });
+        assert_eq!(fold(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_fold_boolean_equality() {
+        let program = leo!(function main(a: bool) {
+            let b = 12u8 > 3u8;
+            let c = 12u8 < 3u8;
+            let d = b == c;
+            let e = a == b;
+        });
+        let expected = leo!(function main(a: bool) {
+            let b = true;
+            let c = false;
+            let d = false;
+            let e = a == b;
+        });
+        assert_eq!(fold(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_fold_around_unknown_identifiers() {
+        let program = leo!(function main(d: u8) {
+            let a = 2u8;
+            let c = 5u8 + d * (2u8 + 3u8);
+            let e = d + a * 3u8;
+            let f = (d + (a + 1u8)) == a;
+        });
+        let expected = leo!(function main(d: u8) {
+            let a = 2u8;
+            let c = 5u8 + d * 5u8;
+            let e = d + 6u8;
+            let f = (d + 3u8) == a;
+        });
+        assert_eq!(fold(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_fold_follows_precedence() {
+        let program = leo!(function main(d: u8) {
+            let c = d * 2u8 + 3u8;
+            let e = d - 1u8 - 1u8;
+            let f = 2u8 * 3u8 + d;
+            let g = d + 2u8 * 3u8;
+            let h = 10u8 - 6u8 - d;
+            let i = d == 1u8 + 2u8;
+        });
+        //only operations whose own operands are known fold
+        let expected = leo!(function main(d: u8) {
+            let c = d * 2u8 + 3u8;
+            let e = d - 1u8 - 1u8;
+            let f = 6u8 + d;
+            let g = d + 6u8;
+            let h = 4u8 - d;
+            let i = d == 3u8;
+        });
+        assert_eq!(fold(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_fold_parentheses() {
+        let program = leo!(function main(d: u8) {
+            let a = (1u8 + 2u8) * 3u8;
+            let b = ((a)) + (d);
+            let c = (d * (a - 9u8)) / 2u8;
+        });
+        let expected = leo!(function main(d: u8) {
+            let a = 9u8;
+            let b = 9u8 + d;
+            let c = (d * 0u8) / 2u8;
+        });
+        assert_eq!(fold(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_errors_inside_parentheses() {
+        let program = leo!(function main(d: u8) {
+            let a = 9u8;
+            let c = d + (1u8 / (a - 9u8));
+        });
+        assert_eq!(fold(program), Err(CompilerError::DivByZero));
+    }
+
+    #[test]
+    fn test_errors_next_to_unknown_identifiers() {
+        let program = leo!(function main(d: u8) {
+            let c = d + 200u8 * 2u8;
+        });
+        assert_eq!(fold(program), Err(CompilerError::Overflow));
+    }
+
+    #[test]
+    fn test_inline_known_branch() {
+        let program = leo!(function main() {
+            let a = 2u8;
+            if a > 1u8 {
+                let b = a + 1u8;
+            } else {
+                let c = 0u8;
+            }
+        });
+        let expected = leo!(function main() {
+            let a = 2u8;
+            let b = 3u8;
+        });
+        let mut warnings = Vec::new();
+        assert_eq!(fold_with_warnings(program, &mut warnings), Ok(expected));
+        assert_eq!(warnings, vec![CompilerWarning::ConstantCondition(true)]);
+    }
+
+    #[test]
+    fn test_fold_inside_unknown_branches() {
+        let program = leo!(function main(flag: bool) {
+            let a = 2u8;
+            if flag {
+                let b = a * 3u8;
+                if b > 5u8 {
+                    let c = b + 1u8;
                 } else {
-                    Ok(Boolean(false))
+                    let c = 0u8;
                 }
+            } else {
+                let b = a - 2u8;
+                let c = flag == (b == 0u8);
             }
-            _ => return Err(CompilerError::MismatchType),
-        },
-        _ => return Err(CompilerError::MismatchType),
+            let b = a + 1u8;
+        });
+        let expected = leo!(function main(flag: bool) {
+            let a = 2u8;
+            if flag {
+                let b = 6u8;
+                let c = 7u8;
+            } else {
+                let b = 0u8;
+                let c = flag == true;
+            }
+            let b = 3u8;
+        });
+        let mut warnings = Vec::new();
+        assert_eq!(fold_with_warnings(program, &mut warnings), Ok(expected));
+        assert_eq!(warnings, vec![CompilerWarning::ConstantCondition(true)]);
     }
-}
 
-//helper function to attempt == and handle errors
-fn eq_bool(v1: Value, v2: Value) -> Result<Value> {
-    match v1 {
-        Integer(x) => match v2 {
-            Integer(y) => {
-                if x == y {
-                    Ok(Boolean(true))
+    #[test]
+    fn test_fold_reassignments() {
+        let program = leo!(function main(d: u8) {
+            let a = 1u8;
+            a = a + 1u8;
+            let b = a * 2u8;
+            a = d;
+            let c = a + 1u8;
+        });
+        let expected = leo!(function main(d: u8) {
+            let a = 1u8;
+            a = 2u8;
+            let b = 4u8;
+            a = d;
+            let c = a + 1u8;
+        });
+        assert_eq!(fold(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_join_after_unknown_branch() {
+        let program = leo!(function main(flag: bool) {
+            let a = 1u8;
+            let b = 2u8;
+            let c = 3u8;
+            if flag {
+                a = 5u8;
+                b = 4u8;
+                let d = 0u8;
+                d = 1u8;
+            } else {
+                a = 5u8;
+                c = c + 1u8;
+            }
+            let e = a + 1u8;
+            let f = b + 1u8;
+            let g = c + 1u8;
+        });
+        let expected = leo!(function main(flag: bool) {
+            let a = 1u8;
+            let b = 2u8;
+            let c = 3u8;
+            if flag {
+                a = 5u8;
+                b = 4u8;
+                let d = 0u8;
+                d = 1u8;
+            } else {
+                a = 5u8;
+                c = 4u8;
+            }
+            let e = 6u8;
+            let f = b + 1u8;
+            let g = c + 1u8;
+        });
+        assert_eq!(fold(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_join_nested_branches() {
+        let program = leo!(function main(x: bool, y: bool) {
+            let a = 1u8;
+            let b = 1u8;
+            if x {
+                if y {
+                    a = 2u8;
+                    b = 3u8;
                 } else {
-                    Ok(Boolean(false))
+                    a = 2u8;
                 }
+                let c = a;
+            } else {
+                a = 2u8;
             }
-            _ => return Err(CompilerError::MismatchType),
-        },
-        _ => return Err(CompilerError::MismatchType),
+            let d = a;
+            let e = b;
+        });
+        let expected = leo!(function main(x: bool, y: bool) {
+            let a = 1u8;
+            let b = 1u8;
+            if x {
+                if y {
+                    a = 2u8;
+                    b = 3u8;
+                } else {
+                    a = 2u8;
+                }
+                let c = 2u8;
+            } else {
+                a = 2u8;
+            }
+            let d = 2u8;
+            let e = b;
+        });
+        assert_eq!(fold(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_rename_branch_bindings_on_clash() {
+        let program = leo!(function main(x: u8) {
+            if false {
+                let a = 1u8;
+            } else {
+                let a = x;
+                a = a + 2u8;
+                let a_1 = a;
+            }
+            let a = 3u8;
+        });
+        //`a_1` is taken, so `a` becomes `a_2`
+        let expected = leo!(function main(x: u8) {
+            let a_2 = x;
+            a_2 = a_2 + 2u8;
+            let a_1 = a_2;
+            let a = 3u8;
+        });
+        assert_eq!(fold(program.clone()), Ok(expected.clone()));
+        assert_eq!(eliminate_branches(program, &mut Vec::new()), expected);
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        let program = leo!(function main() {
+            let a = 200u8 + 100u8;
+        });
+        assert_eq!(fold(program), Err(CompilerError::Overflow));
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use super::*;
+    use crate::ast::builder::*;
+    use std::time::Instant;
+
+    //generates a program mixing foldable bindings, bindings on an unknown input and branches
+    fn generate_program(statements: usize) -> Program {
+        let mut builder = ProgramBuilder::new("main").input("a", Type::U8);
+        for i in 0..statements {
+            let name = format!("v{}", i);
+            let statement = match i % 4 {
+                0 => assign(&name, binary(int(3), Operator::Multiply, int(4))),
+                1 => assign(
+                    &name,
+                    binary(ident(&format!("v{}", i - 1)), Operator::Add, int(1)),
+                ),
+                2 => assign(
+                    &name,
+                    binary(ident("a"), Operator::Add, ident(&format!("v{}", i - 1))),
+                ),
+                _ => if_else(
+                    binary(ident(&format!("v{}", i - 2)), Operator::GreaterThan, int(5)),
+                    vec![assign(&format!("t{}", i), ident(&format!("v{}", i - 1)))],
+                    vec![assign(&format!("t{}", i), int(0))],
+                ),
+            };
+            builder = builder.statement(statement);
+        }
+        builder.build()
+    }
+
+    //run with `cargo test --release -- --ignored --nocapture bench_fold`
+    #[test]
+    #[ignore]
+    fn bench_fold_large_program() {
+        let statements = 400_000;
+        let program = generate_program(statements);
+        let start = Instant::now();
+        let folded = fold(program).unwrap();
+        let elapsed = start.elapsed();
+        println!(
+            "folded {} statements in {:?} ({:.0} statements/s)",
+            statements,
+            elapsed,
+            statements as f64 / elapsed.as_secs_f64()
+        );
+        assert!(!folded.statements.is_empty());
     }
 }
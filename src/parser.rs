@@ -1,6 +1,6 @@
 use crate::ast::*;
-use pest::error::Error;
-use pest::Parser;
+use pest::error::{Error, ErrorVariant};
+use pest::{Parser, Position};
 
 // The pest parser for Leo
 
@@ -10,12 +10,30 @@ pub struct LeoParser;
 
 // Functions to parse a Leo code string into a Leo AST
 
-pub fn parse(source: &str) -> Result<Program, Error<Rule>> {
+type ParseResult<T> = Result<T, Box<Error<Rule>>>;
+
+//how deeply parentheses and blocks may nest by default
+pub const DEFAULT_NESTING_LIMIT: usize = 256;
+
+pub fn parse(source: &str) -> Result<Program, Box<Error<Rule>>> {
+    parse_with_nesting_limit(source, DEFAULT_NESTING_LIMIT)
+}
+
+//same as parse, rejecting source with parentheses or blocks nested deeper than the limit
+pub fn parse_with_nesting_limit(
+    source: &str,
+    nesting_limit: usize,
+) -> Result<Program, Box<Error<Rule>>> {
+    let depth = check_nesting(source, nesting_limit)?;
+    grow_stack_for_depth(depth, || parse_program(source))
+}
+
+fn parse_program(source: &str) -> ParseResult<Program> {
     let mut name = String::new();
     let mut inputs = Vec::new();
     let mut statements = Vec::new();
 
-    let pairs = LeoParser::parse(Rule::program, source)?;
+    let pairs = LeoParser::parse(Rule::program, source).map_err(Box::new)?;
 
     for pair in pairs {
         match pair.as_rule() {
@@ -26,37 +44,64 @@ pub fn parse(source: &str) -> Result<Program, Error<Rule>> {
                 name = pair.next().unwrap().as_str().to_string();
 
                 // Parse function inputs if any
-                if pair.next().is_some() {
-                    inputs = parse_inputs(pair.next().unwrap());
+                if let Some(pair) = pair.next() {
+                    inputs = parse_inputs(pair);
                 }
             }
             Rule::statement => {
-                statements.push(parse_statement(pair.into_inner().next().unwrap()));
+                statements.push(parse_statement(pair.into_inner().next().unwrap())?);
             }
             _ => {}
         }
     }
     Ok(Program {
-        name,
+        name: Symbol::new(&name),
         inputs,
         statements,
     })
 }
 
+//pest and the functions below recurse once per nested parenthesis or block, so deep nesting
+//is reported as an error at the first opening that goes past the limit, and the deepest level
+//is returned to size the stack for the rest
+fn check_nesting(source: &str, nesting_limit: usize) -> ParseResult<usize> {
+    let mut depth = 0;
+    let mut deepest = 0;
+    let mut in_comment = false;
+    for (position, character) in source.char_indices() {
+        match character {
+            '\n' => in_comment = false,
+            _ if in_comment => {}
+            'N' if source[position..].starts_with("NB.") => in_comment = true,
+            '(' | '{' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+                if depth > nesting_limit {
+                    let message = format!("nesting exceeds the limit of {} levels", nesting_limit);
+                    return Err(Box::new(Error::new_from_pos(
+                        ErrorVariant::CustomError { message },
+                        Position::new(source, position).unwrap(),
+                    )));
+                }
+            }
+            ')' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Ok(deepest)
+}
+
 fn parse_inputs(pair: pest::iterators::Pair<Rule>) -> Vec<Input> {
     let mut inputs = Vec::new();
 
     for pair in pair.into_inner() {
-        match pair.as_rule() {
-            Rule::input => {
-                let mut pair = pair.into_inner();
+        if pair.as_rule() == Rule::input {
+            let mut pair = pair.into_inner();
 
-                let name = pair.next().unwrap().as_str().to_string();
-                let input_type = parse_type(pair.next().unwrap());
+            let name = Symbol::new(pair.next().unwrap().as_str());
+            let input_type = parse_type(pair.next().unwrap());
 
-                inputs.push(Input { name, input_type });
-            }
-            _ => {}
+            inputs.push(Input { name, input_type });
         }
     }
 
@@ -66,108 +111,179 @@ fn parse_inputs(pair: pest::iterators::Pair<Rule>) -> Vec<Input> {
 fn parse_type(pair: pest::iterators::Pair<Rule>) -> Type {
     match pair.as_str() {
         "u8" => Type::U8,
+        "bool" => Type::Bool,
+        "field" => Type::Field,
+        "scalar" => Type::Scalar,
+        "group" => Type::Group,
+        "address" => Type::Address,
         _ => panic!("failed to parse type"),
     }
 }
 
-fn parse_statement(pair: pest::iterators::Pair<Rule>) -> Statement {
+fn parse_statement(pair: pest::iterators::Pair<Rule>) -> ParseResult<Statement> {
+    let span = parse_span(&pair);
     match pair.as_rule() {
         Rule::assign => {
             let mut pair = pair.into_inner();
 
-            let variable = pair.next().unwrap().as_str().to_string();
-            let expression = parse_expression(pair.next().unwrap());
+            let variable = Symbol::new(pair.next().unwrap().as_str());
+            let expression = parse_expression(pair.next().unwrap())?;
 
-            Statement::Assign {
+            Ok(Statement::Assign {
                 variable,
                 expression,
-            }
+                span,
+            })
+        }
+        Rule::reassign => {
+            let mut pair = pair.into_inner();
+
+            let variable = Symbol::new(pair.next().unwrap().as_str());
+            let expression = parse_expression(pair.next().unwrap())?;
+
+            Ok(Statement::Reassign {
+                variable,
+                expression,
+                span,
+            })
+        }
+        Rule::return_statement => {
+            let expression = parse_expression(pair.into_inner().next().unwrap())?;
+            Ok(Statement::Return { expression, span })
+        }
+        Rule::assertion => {
+            let expression = parse_expression(pair.into_inner().next().unwrap())?;
+            Ok(Statement::Assert { expression, span })
         }
         Rule::branchif => {
             let mut pair = pair.into_inner();
-            let expression = parse_expression(pair.next().unwrap());
+            let expression = parse_expression(pair.next().unwrap())?;
             let code_bundle_a = pair.next().unwrap().into_inner();
             let mut statements_a = Vec::new();
             for pair in code_bundle_a {
-                statements_a.push(parse_statement(pair.into_inner().next().unwrap()))
+                statements_a.push(parse_statement(pair.into_inner().next().unwrap())?)
             }
             let code_bundle_b = pair.next().unwrap().into_inner();
             let mut statements_b = Vec::new();
             for pair in code_bundle_b {
-                statements_b.push(parse_statement(pair.into_inner().next().unwrap()))
-            }
-            Statement::If {
-                expression: expression,
-                statements_a: statements_a,
-                statements_b: statements_b,
+                statements_b.push(parse_statement(pair.into_inner().next().unwrap())?)
             }
+            Ok(Statement::If {
+                expression,
+                statements_a,
+                statements_b,
+                span,
+            })
         }
         _ => panic!("failed to parse statement"),
     }
 }
 
-fn parse_expression(pair: pest::iterators::Pair<Rule>) -> Expression {
+//where a statement was written, without the `;` that ends it. Statements never end with a
+//newline, so their last character is the one before the end position.
+fn parse_span(pair: &pest::iterators::Pair<Rule>) -> SourceSpan {
+    let span = pair.as_span();
+    let (start_line, start_column) = span.start_pos().line_col();
+    let (end_line, end_column) = span.end_pos().line_col();
+    SourceSpan(Some(Span {
+        start_line,
+        start_column,
+        end_line,
+        end_column: end_column - 1,
+    }))
+}
+
+fn parse_expression(pair: pest::iterators::Pair<Rule>) -> ParseResult<Expression> {
     match pair.as_rule() {
         Rule::expression => {
-            let pair = pair.into_inner().next().unwrap();
-
-            match pair.as_rule() {
-                Rule::binary => {
-                    let mut pair = pair.into_inner();
-
-                    let left = parse_value(pair.next().unwrap());
-                    let operator = parse_operator(pair.next().unwrap());
-                    let right = Box::new(parse_expression(pair.next().unwrap()));
-
-                    Expression::Binary {
-                        left,
-                        operator,
-                        right,
-                    }
-                }
-                Rule::value => {
-                    Expression::Value(Box::new(parse_value(pair.into_inner().next().unwrap())))
-                }
-                Rule::integer => Expression::Value(Box::new(parse_value(pair))),
-                Rule::ident => Expression::Value(Box::new(parse_value(pair))),
-                Rule::boolean => Expression::Value(Box::new(parse_value(pair))),
-                _ => panic!("failed to parse inner expression"),
+            //values separated by operators, grouped by precedence and from the left
+            let mut pair = pair.into_inner();
+            let first = parse_value(pair.next().unwrap())?;
+            let mut rest = Vec::new();
+            while let Some(operator) = pair.next() {
+                rest.push((parse_operator(operator), parse_value(pair.next().unwrap())?));
             }
+            Ok(Expression::infix(first, rest))
         }
         _ => panic!("failed to parse expression"),
     }
 }
 
-fn parse_value(pair: pest::iterators::Pair<Rule>) -> Value {
+fn parse_value(pair: pest::iterators::Pair<Rule>) -> ParseResult<Value> {
     match pair.as_rule() {
-        Rule::integer => {
-            // Parse the integer and trim the value type
-            let int_str = pair.as_str();
-            let int_len = int_str.len();
-            let integer = &int_str[..int_len - 2].parse::<u8>().unwrap();
-
-            Value::Integer(*integer)
+        Rule::integer => parse_integer(pair).map(Value::Integer),
+        Rule::field => parse_prime_field(pair).map(Value::Field),
+        Rule::scalar => parse_prime_field(pair).map(Value::Scalar),
+        Rule::group => {
+            let digits = pair.as_str().trim_end_matches("group");
+            let value = digits.parse().expect("the grammar only accepts digits");
+            Ok(Value::Group(Group { value }))
         }
+        Rule::address => Address::parse(pair.as_str())
+            .map(Value::Address)
+            .map_err(|message| {
+                Box::new(Error::new_from_span(
+                    ErrorVariant::CustomError { message },
+                    pair.as_span(),
+                ))
+            }),
         Rule::ident => {
-            let ident = pair.as_str().to_string();
+            let ident = Symbol::new(pair.as_str());
 
-            Value::Identifier(ident)
+            Ok(Value::Identifier(ident))
         }
         Rule::expression => {
-            let mut pair = pair.into_inner();
-
-            let expression = parse_expression(pair.next().unwrap());
+            //parentheses, nested no deeper than the limit checked before parsing
+            let expression = parse_expression(pair)?;
 
-            Value::Expression(Box::new(expression))
+            Ok(Value::Expression(Box::new(expression)))
         }
         Rule::boolean => {
             let bool = pair.as_str().parse::<bool>().unwrap();
-            Value::Boolean(bool)
+            Ok(Value::Boolean(bool))
         }
         _ => panic!("failed to parse value"),
     }
 }
 
+//splits a literal like `12u8` into its value and type, rejecting values the type cannot hold
+fn parse_integer(pair: pest::iterators::Pair<Rule>) -> ParseResult<Integer> {
+    let literal = pair.as_str();
+    let suffix_start = literal
+        .find(|c: char| c.is_ascii_alphabetic())
+        .expect("integer literals end in their type");
+    let integer_type = IntegerType::from_suffix(&literal[suffix_start..])
+        .expect("the grammar only accepts known integer types");
+    let value = literal[..suffix_start]
+        .trim_start_matches('_')
+        .parse()
+        .expect("the grammar only accepts digits");
+    Integer::new(integer_type, value).ok_or_else(|| {
+        let message = format!(
+            "value {} is out of range for {}",
+            &literal[..suffix_start],
+            integer_type
+        );
+        Box::new(Error::new_from_span(
+            ErrorVariant::CustomError { message },
+            pair.as_span(),
+        ))
+    })
+}
+
+//reads a literal like `12field` or `12scalar`, rejecting values that are not below the modulus
+fn parse_prime_field<T: PrimeField>(pair: pest::iterators::Pair<Rule>) -> ParseResult<T> {
+    let digits = pair.as_str().trim_end_matches(T::NAME);
+    let value = digits.parse().expect("the grammar only accepts digits");
+    T::new(value).ok_or_else(|| {
+        let message = format!("value {} is out of range for {}", digits, T::NAME);
+        Box::new(Error::new_from_span(
+            ErrorVariant::CustomError { message },
+            pair.as_span(),
+        ))
+    })
+}
+
 fn parse_operator(pair: pest::iterators::Pair<Rule>) -> Operator {
     match pair.as_str() {
         "+" => Operator::Add,
@@ -180,3 +296,101 @@ fn parse_operator(pair: pest::iterators::Pair<Rule>) -> Operator {
         _ => panic!("failed to parse operator"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fold;
+
+    #[test]
+    fn test_long_chain() {
+        let chain = vec!["1u8"; 100_000].join(" * ");
+        let source = format!("function main(a: u8) {{\n    let b = {} + a;\n}}", chain);
+        let program = parse(&source).unwrap();
+        assert_eq!(program.to_string(), source);
+        //the multiplications group before the addition, and fold away
+        let folded = fold(program).unwrap();
+        assert_eq!(
+            folded.to_string(),
+            "function main(a: u8) {\n    let b = 1u8 + a;\n}"
+        );
+    }
+
+    #[test]
+    fn test_parentheses() {
+        let source = "function main(a: u8) {\n    let b = (a + (1u8 * a)) - ((2u8));\n}";
+        assert_eq!(parse(source).unwrap().to_string(), source);
+    }
+
+    #[test]
+    fn test_reassignment() {
+        let source = "function main(a: u8) {\n    a = a + 1u8;\n    let b = a;\n}";
+        let program = parse(source).unwrap();
+        assert_eq!(
+            program.statements[0],
+            crate::ast::builder::reassign("a", crate::leo!(a + 1u8))
+        );
+        assert_eq!(program.to_string(), source);
+    }
+
+    #[test]
+    fn test_outputs() {
+        let source = "function main(a: u8) {\n    assert(a > 1u8);\n    return (a + 1u8);\n}";
+        let program = parse(source).unwrap();
+        assert_eq!(
+            program.statements,
+            vec![
+                crate::ast::builder::assert(crate::leo!(a > 1u8)),
+                crate::ast::builder::return_value(crate::leo!((a + 1u8))),
+            ]
+        );
+        assert_eq!(program.to_string(), source);
+    }
+
+    #[test]
+    fn test_integer_out_of_range() {
+        let error = parse("function main() {\n    let a = 1u8 + 256u8;\n}").unwrap_err();
+        assert_eq!(
+            error.line_col,
+            pest::error::LineColLocation::Span((2, 19), (2, 24))
+        );
+        assert_eq!(error.variant.message(), "value 256 is out of range for u8");
+    }
+
+    #[test]
+    fn test_field_out_of_range() {
+        let modulus = crate::ast::field::FIELD_MODULUS;
+        let error = parse(&format!(
+            "function main() {{\n    let a = {}field;\n}}",
+            modulus
+        ))
+        .unwrap_err();
+        assert_eq!(
+            error.variant.message(),
+            format!("value {} is out of range for field", modulus)
+        );
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        let address = "aleo1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq3ljyzq";
+        let error =
+            parse(&format!("function main() {{\n    let a = {};\n}}", address)).unwrap_err();
+        assert_eq!(
+            error.variant.message(),
+            format!("invalid address `{}`: the checksum does not match", address)
+        );
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let source = "function main() {\n    let a = ((1u8));\n}";
+        assert!(parse_with_nesting_limit(source, 3).is_ok());
+        let error = parse_with_nesting_limit(source, 2).unwrap_err();
+        assert_eq!(error.line_col, pest::error::LineColLocation::Pos((2, 14)));
+        assert_eq!(
+            error.variant.message(),
+            "nesting exceeds the limit of 2 levels"
+        );
+    }
+}
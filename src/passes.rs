@@ -0,0 +1,290 @@
+use crate::ast::*;
+use crate::error::{CompilerError, CompilerWarning};
+use crate::{
+    eliminate_branches, eliminate_common_subexpressions, eliminate_dead_code,
+    fold_keeping_branches, fold_with_warnings, propagate_copies, reduce_strength, simplify,
+};
+use clap::ValueEnum;
+
+type Result<T> = std::result::Result<T, CompilerError>;
+
+// Running the optimization passes until the program stops changing.
+//
+// Each pass can expose work for the others: inlining an `if` gives bindings
+// whose values fold further, the simplifier turns `a - a` into a literal that
+// folds, and copy propagation leaves aliases for dead code elimination. The
+// pass manager runs its pipeline again and again until a whole run leaves the
+// program unchanged, and stops with a warning after `max_iterations` runs.
+//
+// Folding inlines the branch of an `if` whose condition it folds when branch
+// elimination is part of the pipeline, so values from the branch fold in the
+// same run. Without it, the `if` is kept with only the branch that runs folded,
+// and its condition is reported all the same.
+
+/// A pass over a checked program.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum Pass {
+    /// [`fold_with_warnings`]
+    Fold,
+    /// [`eliminate_branches`]
+    BranchElimination,
+    /// [`eliminate_common_subexpressions`]
+    CommonSubexpressions,
+    /// [`propagate_copies`]
+    CopyPropagation,
+    /// [`simplify`]
+    Simplify,
+    /// [`reduce_strength`]
+    StrengthReduction,
+    /// [`eliminate_dead_code`]
+    DeadCode,
+}
+
+/// The passes run by default, in order, which are those of [`OptLevel::O3`].
+pub const DEFAULT_PASSES: [Pass; 7] = [
+    Pass::Fold,
+    Pass::BranchElimination,
+    Pass::CommonSubexpressions,
+    Pass::CopyPropagation,
+    Pass::Simplify,
+    Pass::StrengthReduction,
+    Pass::DeadCode,
+];
+
+/// Predefined pipelines, from none of the passes to all of them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum OptLevel {
+    /// No optimization, the program is only checked.
+    #[value(name = "0")]
+    O0,
+    /// Folding and branch elimination.
+    #[value(name = "1")]
+    O1,
+    /// Adds simplification, copy propagation and dead code elimination.
+    #[value(name = "2")]
+    O2,
+    /// Adds common subexpression elimination and strength reduction.
+    #[default]
+    #[value(name = "3")]
+    O3,
+}
+
+impl OptLevel {
+    /// The passes run at this level, in order.
+    pub fn passes(self) -> Vec<Pass> {
+        let level = match self {
+            OptLevel::O0 => 0,
+            OptLevel::O1 => 1,
+            OptLevel::O2 => 2,
+            OptLevel::O3 => 3,
+        };
+        DEFAULT_PASSES
+            .into_iter()
+            .filter(|pass| pass.level() <= level)
+            .collect()
+    }
+}
+
+impl Pass {
+    //the lowest optimization level that runs the pass
+    fn level(self) -> u8 {
+        match self {
+            Pass::Fold | Pass::BranchElimination => 1,
+            Pass::Simplify | Pass::CopyPropagation | Pass::DeadCode => 2,
+            Pass::CommonSubexpressions | Pass::StrengthReduction => 3,
+        }
+    }
+}
+
+/// How many times the pipeline runs by default before giving up on a fixed point.
+pub const DEFAULT_MAX_ITERATIONS: usize = 16;
+
+/// A pipeline of passes, run until the program stops changing.
+#[derive(Clone, Debug)]
+pub struct PassManager {
+    pub passes: Vec<Pass>,
+    pub max_iterations: usize,
+    /// Variables that [`Pass::DeadCode`] treats as outputs.
+    pub keep: Vec<Symbol>,
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        PassManager {
+            passes: DEFAULT_PASSES.to_vec(),
+            max_iterations: DEFAULT_MAX_ITERATIONS,
+            keep: Vec::new(),
+        }
+    }
+}
+
+/// The result of running a [`PassManager`].
+#[derive(Debug, PartialEq)]
+pub struct Optimization {
+    pub program: Program,
+    pub warnings: Vec<CompilerWarning>,
+    /// How many times the pipeline ran, including the run that changed nothing.
+    pub iterations: usize,
+}
+
+impl PassManager {
+    /// Runs the pipeline over a checked program until it reaches a fixed point.
+    pub fn run(&self, program: Program) -> Result<Optimization> {
+        let mut program = program;
+        let mut warnings = Vec::new();
+        for iteration in 1..=self.max_iterations {
+            let previous = program.clone();
+            for pass in &self.passes {
+                program = self.run_pass(*pass, program, &mut warnings)?;
+            }
+            if program == previous {
+                return Ok(Optimization {
+                    program,
+                    warnings,
+                    iterations: iteration,
+                });
+            }
+        }
+        warnings.push(CompilerWarning::IterationLimit(self.max_iterations));
+        Ok(Optimization {
+            program,
+            warnings,
+            iterations: self.max_iterations,
+        })
+    }
+
+    fn run_pass(
+        &self,
+        pass: Pass,
+        program: Program,
+        warnings: &mut Vec<CompilerWarning>,
+    ) -> Result<Program> {
+        match pass {
+            Pass::Fold if self.passes.contains(&Pass::BranchElimination) => {
+                fold_with_warnings(program, warnings)
+            }
+            Pass::Fold => fold_keeping_branches(program, warnings),
+            Pass::BranchElimination => Ok(eliminate_branches(program, warnings)),
+            Pass::CommonSubexpressions => Ok(eliminate_common_subexpressions(program)),
+            Pass::CopyPropagation => Ok(propagate_copies(program)),
+            Pass::Simplify => simplify(program),
+            Pass::StrengthReduction => Ok(reduce_strength(program)),
+            Pass::DeadCode => Ok(eliminate_dead_code(program, &self.keep)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_runs_until_nothing_changes() {
+        let program = parse(
+            "function main(a: u8) {\n    let b = a - a;\n    let c = b + 1u8;\n    return c;\n}",
+        )
+        .unwrap();
+        let expected = parse("function main(a: u8) {\n    return 1u8;\n}").unwrap();
+        //the simplifier finds `b` in the first run, which folds `c` in the second
+        assert_eq!(
+            PassManager::default().run(program),
+            Ok(Optimization {
+                program: expected,
+                warnings: Vec::new(),
+                iterations: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_iteration_limit() {
+        let program = parse(
+            "function main(a: u8) {\n    let b = a - a;\n    let c = b + 1u8;\n    return c;\n}",
+        )
+        .unwrap();
+        let passes = PassManager {
+            max_iterations: 1,
+            ..PassManager::default()
+        };
+        let optimization = passes.run(program).unwrap();
+        assert_eq!(
+            optimization.program.to_string(),
+            "function main(a: u8) {\n    let b = 0u8;\n    let c = b + 1u8;\n    return c;\n}"
+        );
+        assert_eq!(
+            optimization.warnings,
+            vec![CompilerWarning::IterationLimit(1)]
+        );
+    }
+
+    #[test]
+    fn test_warnings_from_later_iterations() {
+        //the condition is only known once the simplifier has turned `b` into a literal
+        let program = parse("function main(a: u8) {\n    let b = a - a;\n    if b == 0u8 {\n        let c = 1u8;\n    } else {\n        let c = 2u8;\n    }\n}").unwrap();
+        let optimization = PassManager::default().run(program).unwrap();
+        assert_eq!(
+            optimization.program.to_string(),
+            "function main(a: u8) {\n    let b = 0u8;\n    let c = 1u8;\n}"
+        );
+        assert_eq!(
+            optimization.warnings,
+            vec![CompilerWarning::ConstantCondition(true)]
+        );
+        assert_eq!(optimization.iterations, 3);
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_eq!(OptLevel::O0.passes(), Vec::new());
+        assert_eq!(
+            OptLevel::O1.passes(),
+            vec![Pass::Fold, Pass::BranchElimination]
+        );
+        assert_eq!(OptLevel::O3.passes(), DEFAULT_PASSES.to_vec());
+    }
+
+    #[test]
+    fn test_folding_without_branch_elimination() {
+        //the branch that never runs is not folded, so its overflow is not reported
+        let source = "function main(a: u8) {\n    let b = 1u8 + 1u8;\n    if b > 1u8 {\n        let c = b + a;\n    } else {\n        let c = 255u8 + 1u8;\n    }\n}";
+        let program = parse(source).unwrap();
+        let passes = PassManager {
+            passes: vec![Pass::Fold],
+            ..PassManager::default()
+        };
+        let optimization = passes.run(program.clone()).unwrap();
+        let expected = parse("function main(a: u8) {\n    let b = 2u8;\n    if true {\n        let c = b + a;\n    } else {\n        let c = 255u8 + 1u8;\n    }\n}").unwrap();
+        assert_eq!(optimization.program, expected);
+        //the condition is reported once, as with branch elimination
+        assert_eq!(
+            optimization.warnings,
+            vec![CompilerWarning::ConstantCondition(true)]
+        );
+        let optimization = PassManager {
+            passes: OptLevel::O1.passes(),
+            ..PassManager::default()
+        }
+        .run(program)
+        .unwrap();
+        assert_eq!(
+            optimization.warnings,
+            vec![CompilerWarning::ConstantCondition(true)]
+        );
+
+        //branch elimination on its own inlines the branch once the condition is a literal
+        let passes = PassManager {
+            passes: vec![Pass::BranchElimination],
+            ..PassManager::default()
+        };
+        let optimization = passes.run(expected).unwrap();
+        assert_eq!(
+            optimization.program.to_string(),
+            "function main(a: u8) {\n    let b = 2u8;\n    let c = b + a;\n}"
+        );
+        assert_eq!(
+            optimization.warnings,
+            vec![CompilerWarning::ConstantCondition(true)]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use crate::ast::*;
+use crate::error::{Binding, CompilerError};
+use crate::symbol_table::SymbolTable;
+
+type Result<T> = std::result::Result<T, CompilerError>;
+
+//checks that every identifier in the program refers to a function input or a previously bound local.
+pub fn resolve(program: &Program) -> Result<()> {
+    //names visible at the current statement and where they were bound
+    let mut bindings = SymbolTable::new();
+    for input in &program.inputs {
+        let binding = Binding::Input {
+            function: program.name.to_string(),
+        };
+        declare(&mut bindings, input.name.clone(), binding)?;
+    }
+    resolve_statements(&program.statements, &mut bindings)
+}
+
+fn resolve_statements(statements: &[Statement], bindings: &mut SymbolTable<Binding>) -> Result<()> {
+    for statement in statements {
+        //errors are located at the innermost statement they are raised in
+        resolve_statement(statement, bindings)
+            .map_err(|error| error.in_statement(statement.span()))?;
+    }
+    Ok(())
+}
+
+fn resolve_statement(statement: &Statement, bindings: &mut SymbolTable<Binding>) -> Result<()> {
+    match statement {
+        Statement::Assign {
+            variable,
+            expression,
+            ..
+        } => {
+            //the variable is not in scope in its own definition
+            resolve_expression(expression, bindings)?;
+            let binding = Binding::Local {
+                statement: statement.to_string(),
+            };
+            declare(bindings, variable.clone(), binding)?;
+        }
+        Statement::Reassign {
+            variable,
+            expression,
+            ..
+        } => {
+            resolve_expression(expression, bindings)?;
+            resolve_value(&Value::Identifier(variable.clone()), bindings)?;
+        }
+        Statement::Return { expression, .. } | Statement::Assert { expression, .. } => {
+            resolve_expression(expression, bindings)?;
+        }
+        Statement::If {
+            expression,
+            statements_a,
+            statements_b,
+            ..
+        } => {
+            resolve_expression(expression, bindings)?;
+            //bindings made inside a branch are not visible after the if
+            for branch in [statements_a, statements_b] {
+                bindings.enter_scope();
+                let resolved = resolve_statements(branch, bindings);
+                bindings.exit_scope();
+                resolved?;
+            }
+        }
+    }
+    Ok(())
+}
+
+//binds a name in the innermost scope, pointing at the previous binding if it is already visible
+fn declare(bindings: &mut SymbolTable<Binding>, name: Symbol, binding: Binding) -> Result<()> {
+    bindings
+        .declare(name.clone(), binding)
+        .map_err(|previous| CompilerError::Redeclaration {
+            name: name.to_string(),
+            previous: Some(previous.clone()),
+        })
+}
+
+fn resolve_expression(expression: &Expression, bindings: &SymbolTable<Binding>) -> Result<()> {
+    for value in expression.values() {
+        resolve_value(value, bindings)?;
+    }
+    Ok(())
+}
+
+fn resolve_value(value: &Value, bindings: &SymbolTable<Binding>) -> Result<()> {
+    match value {
+        Value::Identifier(name) => {
+            if bindings.lookup(name).is_some() {
+                Ok(())
+            } else {
+                Err(CompilerError::UndefinedVariable {
+                    name: name.to_string(),
+                    similar: find_similar(name.as_str(), bindings),
+                })
+            }
+        }
+        Value::Expression(expression) => resolve_expression(expression, bindings),
+        Value::Integer(_)
+        | Value::Field(_)
+        | Value::Scalar(_)
+        | Value::Group(_)
+        | Value::Address(_)
+        | Value::Boolean(_) => Ok(()),
+    }
+}
+
+//finds the closest visible name within a small edit distance, to suggest as a likely typo
+fn find_similar(name: &str, bindings: &SymbolTable<Binding>) -> Option<(String, Binding)> {
+    let max_distance = std::cmp::max(name.len(), 3) / 3;
+    bindings
+        .visible()
+        .map(|(candidate, binding)| {
+            (
+                edit_distance(name, candidate.as_str()),
+                candidate.as_str(),
+                binding,
+            )
+        })
+        .filter(|(distance, _, _)| *distance <= max_distance)
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+        .map(|(_, candidate, binding)| (candidate.to_string(), binding.clone()))
+}
+
+//levenshtein distance between two names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_inputs_and_locals_resolve() {
+        let program =
+            parse("function main(a: u8, b: u8) {\n    let c = a + b;\n    let d = c;\n}").unwrap();
+        assert_eq!(resolve(&program), Ok(()));
+    }
+
+    #[test]
+    fn test_similar_input_is_suggested() {
+        let program = parse("function main(value: u8) {\n    let c = valu + 1u8;\n}").unwrap();
+        assert_eq!(
+            resolve(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::UndefinedVariable {
+                name: String::from("valu"),
+                similar: Some((
+                    String::from("value"),
+                    Binding::Input {
+                        function: String::from("main")
+                    }
+                )),
+            })
+        );
+    }
+
+    #[test]
+    fn test_branch_cannot_redeclare_outer_variable() {
+        let program = parse("function main(a: u8) {\n    let b = a;\n    if true {\n        let b = 1u8;\n    } else {\n        let c = 2u8;\n    }\n}").unwrap();
+        assert_eq!(
+            resolve(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::Redeclaration {
+                name: String::from("b"),
+                previous: Some(Binding::Local {
+                    statement: String::from("let b = a;")
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_is_located_at_innermost_statement() {
+        let program = parse("function main(a: u8) {\n    let b = a;\n    if true {\n        let b = 1u8;\n    } else {\n        let c = 2u8;\n    }\n}").unwrap();
+        //`let b = 1u8`, not the if around it
+        assert_eq!(
+            resolve(&program).unwrap_err().span(),
+            Some(Span {
+                start_line: 4,
+                start_column: 9,
+                end_line: 4,
+                end_column: 19,
+            })
+        );
+    }
+
+    #[test]
+    fn test_branch_bindings_do_not_leak() {
+        let program = parse("function main() {\n    if true {\n        let a = 1u8;\n    } else {\n        let b = 2u8;\n    }\n\n    let total = a;\n}").unwrap();
+        assert_eq!(
+            resolve(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::UndefinedVariable {
+                name: String::from("a"),
+                similar: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_reassigned_variable_must_be_bound() {
+        let program = parse("function main(a: u8) {\n    if true {\n        let b = 1u8;\n    } else {\n        a = 2u8;\n    }\n\n    b = a;\n}").unwrap();
+        assert_eq!(
+            resolve(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::UndefinedVariable {
+                name: String::from("b"),
+                similar: Some((
+                    String::from("a"),
+                    Binding::Input {
+                        function: String::from("main")
+                    }
+                )),
+            })
+        );
+    }
+}
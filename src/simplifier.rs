@@ -0,0 +1,333 @@
+use crate::ast::*;
+use crate::error::CompilerError;
+use crate::symbol_table::SymbolTable;
+use crate::typechecker::infer_expression;
+use num_traits::{One, Zero};
+
+type Result<T> = std::result::Result<T, CompilerError>;
+
+// Algebraic identities that hold whatever the value of an unknown operand:
+//
+//   a + 0, 0 + a, a - 0   => a
+//   a * 1, 1 * a, a / 1   => a
+//   a * 0, 0 * a          => 0
+//   a - a                 => 0
+//   a == a                => true
+//
+// Leo checks every operation at run time, so a rewrite must not drop an
+// operation that could fail. The first two groups only drop a literal, the
+// others drop the unknown operand and are only applied when it is a single
+// identifier or literal, never a parenthesized expression that could overflow.
+// Each identity applies to an operation and its own operands, so `b - a - a`,
+// which is `(b - a) - a`, is left alone.
+
+//takes in a checked Program AST and returns a new AST with the identities above simplified.
+pub fn simplify(program: Program) -> Result<Program> {
+    let mut simplifier = Simplifier {
+        types: SymbolTable::new(),
+    };
+    simplifier.reconstruct_program(program)
+}
+
+// Reconstructor that applies the identities, keeping the type of every visible name to
+// know which zero `a - a` is.
+struct Simplifier {
+    types: SymbolTable<Type>,
+}
+
+impl Simplifier {
+    //the result of `left operator right`, if an identity gives it without evaluating the operation
+    fn identity(
+        &self,
+        left: &Expression,
+        operator: &Operator,
+        right: &Expression,
+    ) -> Option<Identity> {
+        let (left_value, right_value) = (left.as_value(), right.as_value());
+        //`a - a` and `a == a`
+        let same_name = match (left_value, right_value) {
+            (Some(Value::Identifier(a)), Some(Value::Identifier(b))) if a == b => Some(a),
+            _ => None,
+        };
+        match operator {
+            Operator::Add if right_value.is_some_and(is_zero) => Some(Identity::Left),
+            Operator::Add if left_value.is_some_and(is_zero) => Some(Identity::Right),
+            Operator::Subtract if right_value.is_some_and(is_zero) => Some(Identity::Left),
+            Operator::Subtract if same_name.is_some() => same_name
+                .and_then(|name| self.types.lookup(name))
+                .and_then(zero)
+                .map(Identity::Value),
+            Operator::Multiply if right_value.is_some_and(is_one) => Some(Identity::Left),
+            Operator::Multiply if left_value.is_some_and(is_one) => Some(Identity::Right),
+            Operator::Multiply
+                if left_value.is_some_and(is_zero) && right_value.is_some_and(cannot_fail) =>
+            {
+                Some(Identity::Value(left_value.unwrap().clone()))
+            }
+            Operator::Multiply
+                if right_value.is_some_and(is_zero) && left_value.is_some_and(cannot_fail) =>
+            {
+                Some(Identity::Value(right_value.unwrap().clone()))
+            }
+            Operator::Divide if right_value.is_some_and(is_one) => Some(Identity::Left),
+            Operator::Equal if same_name.is_some() => Some(Identity::Value(Value::Boolean(true))),
+            _ => None,
+        }
+    }
+}
+
+//what an operation simplifies to
+enum Identity {
+    //the left operand
+    Left,
+    //the right operand
+    Right,
+    //a value known without evaluating either side
+    Value(Value),
+}
+
+impl Reconstructor for Simplifier {
+    type Error = CompilerError;
+
+    fn reconstruct_program(&mut self, program: Program) -> Result<Program> {
+        for input in &program.inputs {
+            self.types
+                .bind(input.name.clone(), input.input_type.clone())?;
+        }
+        rebuild_program(self, program)
+    }
+
+    fn reconstruct_branch(&mut self, statements: Vec<Statement>) -> Result<Vec<Statement>> {
+        self.types.enter_scope();
+        let statements = self.reconstruct_statements(statements);
+        self.types.exit_scope();
+        statements
+    }
+
+    fn reconstruct_statement(&mut self, statement: Statement) -> Result<Vec<Statement>> {
+        let statements = rebuild_statement(self, statement)?;
+        for statement in &statements {
+            if let Statement::Assign {
+                variable,
+                expression,
+                ..
+            } = statement
+            {
+                let value_type = infer_expression(expression, &self.types)?;
+                self.types.bind(variable.clone(), value_type)?;
+            }
+        }
+        Ok(statements)
+    }
+
+    fn reconstruct_expression(&mut self, expression: Expression) -> Result<Expression> {
+        //simplify the operands first, then the operation itself
+        Ok(match rebuild_expression(self, expression)?.into_parts() {
+            Parts::Binary(left, operator, right) => match self.identity(&left, &operator, &right) {
+                Some(Identity::Left) => left,
+                Some(Identity::Right) => right,
+                Some(Identity::Value(value)) => Expression::Value(Box::new(value)),
+                None => Expression::binary(left, operator, right),
+            },
+            Parts::Value(value) => Expression::Value(Box::new(value)),
+        })
+    }
+
+    fn reconstruct_value(&mut self, value: Value) -> Result<Value> {
+        //parentheses left holding a single value are redundant
+        match rebuild_value(self, value)? {
+            Value::Expression(inner) => match inner.into_parts() {
+                Parts::Value(value) => Ok(value),
+                Parts::Binary(left, operator, right) => Ok(Value::Expression(Box::new(
+                    Expression::binary(left, operator, right),
+                ))),
+            },
+            value => Ok(value),
+        }
+    }
+}
+
+fn is_zero(value: &Value) -> bool {
+    match value {
+        Value::Integer(x) => x.value.is_zero(),
+        Value::Field(x) => x.value.is_zero(),
+        Value::Scalar(x) => x.value.is_zero(),
+        Value::Group(x) => x.value.is_zero(),
+        _ => false,
+    }
+}
+
+fn is_one(value: &Value) -> bool {
+    match value {
+        Value::Integer(x) => x.value.is_one(),
+        Value::Field(x) => x.value.is_one(),
+        Value::Scalar(x) => x.value.is_one(),
+        _ => false,
+    }
+}
+
+//values whose evaluation cannot fail at run time, and may be dropped
+fn cannot_fail(value: &Value) -> bool {
+    !matches!(value, Value::Expression(_))
+}
+
+fn zero(value_type: &Type) -> Option<Value> {
+    match value_type {
+        Type::U8 => Some(Value::Integer(Integer::from(0))),
+        Type::Field => Some(Value::Field(Field::from(0))),
+        Type::Scalar => Some(Value::Scalar(Scalar::from(0))),
+        Type::Group => Some(Value::Group(Group::from(0))),
+        Type::Bool | Type::Address => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leo;
+
+    #[test]
+    fn test_add_zero() {
+        let program = leo!(function main(a: u8) {
+            let b = a + 0u8;
+            let c = 0u8 + a;
+        });
+        let expected = leo!(function main(a: u8) {
+            let b = a;
+            let c = a;
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_subtract_zero() {
+        let program = leo!(function main(a: u8) {
+            let b = a - 0u8;
+            let c = 0u8 - a;
+        });
+        let expected = leo!(function main(a: u8) {
+            let b = a;
+            let c = 0u8 - a;
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_multiply_one() {
+        let program = leo!(function main(a: u8) {
+            let b = a * 1u8;
+            let c = 1u8 * a;
+        });
+        let expected = leo!(function main(a: u8) {
+            let b = a;
+            let c = a;
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_divide_one() {
+        let program = leo!(function main(a: u8) {
+            let b = a / 1u8;
+            let c = 1u8 / a;
+        });
+        let expected = leo!(function main(a: u8) {
+            let b = a;
+            let c = 1u8 / a;
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_multiply_zero() {
+        let program = leo!(function main(a: u8, b: u8) {
+            let c = a * 0u8;
+            let d = 0u8 * a;
+            let e = 0u8 * a * b;
+        });
+        let expected = leo!(function main(a: u8, b: u8) {
+            let c = 0u8;
+            let d = 0u8;
+            let e = 0u8;
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_multiply_zero_keeps_checked_operations() {
+        //`a * b` may overflow, so it must still be evaluated
+        let program = leo!(function main(a: u8, b: u8) {
+            let c = (a * b) * 0u8;
+        });
+        assert_eq!(simplify(program.clone()), Ok(program));
+    }
+
+    #[test]
+    fn test_subtract_self() {
+        let program = leo!(function main(a: u8, b: field, c: u8) {
+            let d = a - a;
+            let e = b - b;
+            let f = a - c;
+        });
+        let expected = leo!(function main(a: u8, b: field, c: u8) {
+            let d = 0u8;
+            let e = 0field;
+            let f = a - c;
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_identities_apply_to_operands() {
+        //`b - a - a` subtracts `a` from `b - a`, and `a + b * 0u8` adds `b * 0u8` to `a`
+        let program = leo!(function main(a: u8, b: u8) {
+            let c = b - a - a;
+            let d = a + b * 0u8;
+            let e = a * 1u8 - a;
+            let f = b - (a - a);
+        });
+        let expected = leo!(function main(a: u8, b: u8) {
+            let c = b - a - a;
+            let d = a;
+            let e = 0u8;
+            let f = b;
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_equal_self() {
+        let program = leo!(function main(a: u8, c: bool) {
+            let d = a == a;
+            let e = c == c;
+            let f = (a + 1u8) == (a + 1u8);
+        });
+        let expected = leo!(function main(a: u8, c: bool) {
+            let d = true;
+            let e = true;
+            let f = (a + 1u8) == (a + 1u8);
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+
+    #[test]
+    fn test_nested_identities() {
+        let program = leo!(function main(a: u8) {
+            let b = 1u8 * (a + 0u8) / 1u8;
+            if (a - a) == 0u8 {
+                let c = b * 1u8;
+            } else {
+                let c = 0u8 + b;
+            }
+        });
+        let expected = leo!(function main(a: u8) {
+            let b = a;
+            if 0u8 == 0u8 {
+                let c = b;
+            } else {
+                let c = b;
+            }
+        });
+        assert_eq!(simplify(program), Ok(expected));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::ast::*;
+use crate::cost::operation_cost;
+use num_bigint::BigInt;
+use std::convert::Infallible;
+
+// Strength reduction, replacing operations with cheaper ones that give the
+// same result, including whether it fails:
+//
+//   a * 2, 2 * a                 => a + a
+//   a < min + 1, min + 1 > a     => a == min
+//   a > max - 1, max - 1 < a     => a == max
+//
+// where min and max are the bounds of an integer type. Each rewrite is only
+// made when the cost model rates the new operation cheaper for the type of
+// its operands, e.g. `a * 2u8` becomes `a + a`, but `a * 2field` is kept since
+// scaling a field by a constant is already free. For the same reason there is
+// no rewrite of a field division by a constant into a multiplication by its
+// inverse. Doubling only applies to a single identifier, so no operation is
+// evaluated twice. A rewrite applies to an operation and its own
+// operands, e.g. `x - a * 2u8` becomes `x - (a + a)`.
+
+//takes in a checked Program AST and returns it with operations replaced by cheaper ones
+pub fn reduce_strength(program: Program) -> Program {
+    let Ok(program) = StrengthReducer.reconstruct_program(program);
+    program
+}
+
+// Reconstructor that applies the rewrites to every operation, after its operands. Each rewrite
+// involves a literal operand, which gives the type to look up the cost of the operation with.
+struct StrengthReducer;
+
+impl Reconstructor for StrengthReducer {
+    type Error = Infallible;
+
+    fn reconstruct_expression(
+        &mut self,
+        expression: Expression,
+    ) -> std::result::Result<Expression, Infallible> {
+        Ok(match rebuild_expression(self, expression)?.into_parts() {
+            Parts::Binary(left, operator, right) => reduce(left, operator, right),
+            Parts::Value(value) => Expression::Value(Box::new(value)),
+        })
+    }
+}
+
+//how an operation is rewritten
+enum Reduced {
+    //`a + a` for the identifier `a`
+    Double(Value),
+    //the left side kept, with a new operator and right side
+    Left(Operator, Value),
+    //the right side kept, with a new left side and operator
+    Right(Value, Operator),
+}
+
+//`left operator right`, replaced by a cheaper operation giving the same result if there is one
+fn reduce(left: Expression, operator: Operator, right: Expression) -> Expression {
+    let Some((reduced, operand_type)) = reduction(&left, &operator, &right) else {
+        return Expression::binary(left, operator, right);
+    };
+    let reduced_operator = match &reduced {
+        Reduced::Double(_) => Operator::Add,
+        Reduced::Left(operator, _) | Reduced::Right(_, operator) => operator.clone(),
+    };
+    //the operation replaced has a literal operand, and so has the new one unless it is doubling
+    let reduced_constant = !matches!(reduced, Reduced::Double(_));
+    if operation_cost(&reduced_operator, &operand_type, reduced_constant)
+        >= operation_cost(&operator, &operand_type, true)
+    {
+        return Expression::binary(left, operator, right);
+    }
+    match reduced {
+        Reduced::Double(value) => {
+            Expression::binary(value.clone().into(), Operator::Add, value.into())
+        }
+        Reduced::Left(operator, value) => Expression::binary(left, operator, value.into()),
+        Reduced::Right(value, operator) => Expression::binary(value.into(), operator, right),
+    }
+}
+
+//the rewrite of `left operator right` if one applies, with the type of the operands, which both
+//have the type of the literal the rewrite involves in a checked program
+fn reduction(
+    left: &Expression,
+    operator: &Operator,
+    right: &Expression,
+) -> Option<(Reduced, Type)> {
+    let (left, right) = (left.as_value(), right.as_value());
+    match operator {
+        Operator::Multiply => match (left?, right?) {
+            (a @ Value::Identifier(_), two) | (two, a @ Value::Identifier(_)) if is_two(two) => {
+                Some((Reduced::Double(a.clone()), literal_type(two)))
+            }
+            _ => None,
+        },
+        Operator::LessThan => compare_with_bound(left, right, Bound::Min, Bound::Max),
+        Operator::GreaterThan => compare_with_bound(left, right, Bound::Max, Bound::Min),
+        _ => None,
+    }
+}
+
+//`a < min + 1` and `max - 1 < a` for `<`, with the bounds the other way around for `>`
+fn compare_with_bound(
+    left: Option<&Value>,
+    right: Option<&Value>,
+    right_bound: Bound,
+    left_bound: Bound,
+) -> Option<(Reduced, Type)> {
+    if let Some(limit) = right.and_then(|right| bound(right, right_bound)) {
+        let limit_type = literal_type(&limit);
+        return Some((Reduced::Left(Operator::Equal, limit), limit_type));
+    }
+    let limit = bound(left?, left_bound)?;
+    let limit_type = literal_type(&limit);
+    Some((Reduced::Right(limit, Operator::Equal), limit_type))
+}
+
+fn literal_type(literal: &Value) -> Type {
+    match literal {
+        Value::Integer(x) => Type::from(x.integer_type),
+        Value::Field(_) => Type::Field,
+        Value::Scalar(_) => Type::Scalar,
+        _ => unreachable!("rewrites only involve integer, field and scalar literals"),
+    }
+}
+
+//the bounds of an integer type
+enum Bound {
+    Min,
+    Max,
+}
+
+//the bound of the literal's type when the literal is next to it, e.g. `0u8` for `1u8`
+fn bound(value: &Value, bound: Bound) -> Option<Value> {
+    let Value::Integer(x) = value else {
+        return None;
+    };
+    let (limit, next) = match bound {
+        Bound::Min => (x.integer_type.min(), x.integer_type.min() + 1),
+        Bound::Max => (x.integer_type.max(), x.integer_type.max() - 1),
+    };
+    if x.value != next {
+        return None;
+    }
+    Integer::new(x.integer_type, limit).map(Value::Integer)
+}
+
+fn is_two(value: &Value) -> bool {
+    let two = BigInt::from(2);
+    match value {
+        Value::Integer(x) => x.value == two,
+        Value::Field(x) => x.value == two,
+        Value::Scalar(x) => x.value == two,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leo;
+
+    #[test]
+    fn test_doubling() {
+        let program = leo!(function main(a: u8, b: u8) {
+            let c = a * 2u8;
+            let d = 2u8 * b;
+            let e = (a + b) * 2u8;
+            let f = 2u8 * a + b;
+            let g = a * 4u8;
+        });
+        //`e` would evaluate an operation twice and `g` costs more as additions, `f` is `(2u8 * a) + b`
+        let expected = leo!(function main(a: u8, b: u8) {
+            let c = a + a;
+            let d = b + b;
+            let e = (a + b) * 2u8;
+            let f = a + a + b;
+            let g = a * 4u8;
+        });
+        assert_eq!(reduce_strength(program), expected);
+    }
+
+    #[test]
+    fn test_comparisons_with_bounds() {
+        let program = leo!(function main(a: u8, b: u8) {
+            let c = a < 1u8;
+            let d = 1u8 > a;
+            let e = (a + b) > 254u8;
+            let f = 254u8 < b;
+            let g = a < 2u8;
+        });
+        let expected = leo!(function main(a: u8, b: u8) {
+            let c = a == 0u8;
+            let d = 0u8 == a;
+            let e = (a + b) == 255u8;
+            let f = 255u8 == b;
+            let g = a < 2u8;
+        });
+        assert_eq!(reduce_strength(program), expected);
+    }
+
+    #[test]
+    fn test_field_scaling_is_kept() {
+        let program = leo!(function main(a: field, b: scalar, c: u8) {
+            let d = a / 2field;
+            let e = b / 1scalar;
+            let f = c / 2u8;
+            let g = a * 2field;
+            let h = 2scalar * b;
+        });
+        //scaling by a constant costs no more than the rewrite would
+        assert_eq!(reduce_strength(program.clone()), program);
+    }
+
+    #[test]
+    fn test_chains_and_branches() {
+        let program = leo!(function main(a: u8, x: u8, flag: bool) {
+            let b = a + a * 2u8;
+            let d = x - a * 2u8;
+            if flag {
+                let c = (b * 2u8) < 1u8;
+            } else {
+                let c = b < 1u8;
+            }
+        });
+        //the doubled operand keeps its parentheses, `x - a + a` would be `(x - a) + a`
+        let expected = leo!(function main(a: u8, x: u8, flag: bool) {
+            let b = a + (a + a);
+            let d = x - (a + a);
+            if flag {
+                let c = (b + b) == 0u8;
+            } else {
+                let c = b == 0u8;
+            }
+        });
+        let reduced = reduce_strength(program);
+        assert!(reduced.to_string().contains("let d = x - (a + a);"));
+        assert_eq!(reduced, expected);
+    }
+}
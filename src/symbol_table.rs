@@ -0,0 +1,118 @@
+use crate::ast::Symbol;
+use crate::error::CompilerError;
+use std::collections::HashMap;
+
+// A stack of lexical scopes. The function body is the outermost scope and
+// each `if` branch pushes a new one, so names bound inside a branch are
+// dropped again once the branch has been processed.
+#[derive(Debug, Clone)]
+pub struct SymbolTable<T> {
+    scopes: Vec<HashMap<Symbol, T>>,
+}
+
+impl<T> SymbolTable<T> {
+    pub fn new() -> Self {
+        SymbolTable {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn enter_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    //leaves the innermost scope, returning the names that were bound in it
+    pub fn exit_scope(&mut self) -> HashMap<Symbol, T> {
+        assert!(self.scopes.len() > 1, "cannot exit the function scope");
+        self.scopes.pop().unwrap()
+    }
+
+    //looks a name up from the innermost scope outwards
+    pub fn lookup(&self, name: &Symbol) -> Option<&T> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    //looks a name up like `lookup`, to change the value it is bound to
+    pub fn lookup_mut(&mut self, name: &Symbol) -> Option<&mut T> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+    }
+
+    //binds a name in the innermost scope. Leo does not allow shadowing, so a name that is
+    //already visible from this or any enclosing scope is rejected with the existing binding.
+    pub fn declare(&mut self, name: Symbol, value: T) -> Result<(), &T> {
+        if self.lookup(&name).is_some() {
+            return Err(self.lookup(&name).unwrap());
+        }
+        self.scopes.last_mut().unwrap().insert(name, value);
+        Ok(())
+    }
+
+    //binds a name like `declare`, rejecting a visible one as a redeclaration for passes that
+    //do not point at the previous binding
+    pub fn bind(&mut self, name: Symbol, value: T) -> Result<(), CompilerError> {
+        if self.lookup(&name).is_some() {
+            return Err(CompilerError::Redeclaration {
+                name: name.to_string(),
+                previous: None,
+            });
+        }
+        self.scopes.last_mut().unwrap().insert(name, value);
+        Ok(())
+    }
+
+    //iterates every visible name, innermost scope first
+    pub fn visible(&self) -> impl Iterator<Item = (&Symbol, &T)> {
+        self.scopes.iter().rev().flat_map(|scope| scope.iter())
+    }
+}
+
+impl<T> Default for SymbolTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inner_scope_is_dropped() {
+        let mut table = SymbolTable::new();
+        table.declare(Symbol::new("a"), 1).unwrap();
+        table.enter_scope();
+        table.declare(Symbol::new("b"), 2).unwrap();
+        assert_eq!(table.lookup(&Symbol::new("a")), Some(&1));
+        assert_eq!(table.lookup(&Symbol::new("b")), Some(&2));
+        table.exit_scope();
+        assert_eq!(table.lookup(&Symbol::new("b")), None);
+        table.declare(Symbol::new("b"), 3).unwrap();
+        assert_eq!(table.lookup(&Symbol::new("b")), Some(&3));
+    }
+
+    #[test]
+    fn test_outer_name_cannot_be_redeclared() {
+        let mut table = SymbolTable::new();
+        table.declare(Symbol::new("a"), 1).unwrap();
+        table.enter_scope();
+        assert_eq!(table.declare(Symbol::new("a"), 2), Err(&1));
+        assert_eq!(table.lookup(&Symbol::new("a")), Some(&1));
+    }
+
+    #[test]
+    fn test_bind_reports_redeclaration() {
+        let mut table = SymbolTable::new();
+        table.bind(Symbol::new("a"), 1).unwrap();
+        table.enter_scope();
+        assert_eq!(
+            table.bind(Symbol::new("a"), 2),
+            Err(CompilerError::Redeclaration {
+                name: String::from("a"),
+                previous: None
+            })
+        );
+    }
+}
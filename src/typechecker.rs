@@ -0,0 +1,234 @@
+use crate::ast::*;
+use crate::error::CompilerError;
+use crate::symbol_table::SymbolTable;
+
+type Result<T> = std::result::Result<T, CompilerError>;
+
+//infers the type of every binding and expression, rejecting ill-typed programs before they are folded.
+pub fn check(program: &Program) -> Result<()> {
+    //types of the names visible at the current statement
+    let mut types = SymbolTable::new();
+    for input in &program.inputs {
+        types.bind(input.name.clone(), input.input_type.clone())?;
+    }
+    check_statements(&program.statements, &mut types)
+}
+
+fn check_statements(statements: &[Statement], types: &mut SymbolTable<Type>) -> Result<()> {
+    for statement in statements {
+        //errors are located at the innermost statement they are raised in
+        check_statement(statement, types).map_err(|error| error.in_statement(statement.span()))?;
+    }
+    Ok(())
+}
+
+fn check_statement(statement: &Statement, types: &mut SymbolTable<Type>) -> Result<()> {
+    match statement {
+        Statement::Assign {
+            variable,
+            expression,
+            ..
+        } => {
+            let expression_type = infer_expression(expression, types)?;
+            types.bind(variable.clone(), expression_type)?;
+        }
+        Statement::Reassign {
+            variable,
+            expression,
+            ..
+        } => {
+            let expression_type = infer_expression(expression, types)?;
+            let declared = infer_value(&Value::Identifier(variable.clone()), types)?;
+            if expression_type != declared {
+                return Err(CompilerError::ReassignmentMismatch {
+                    name: variable.to_string(),
+                    expected: declared,
+                    found: expression_type,
+                });
+            }
+        }
+        Statement::Return { expression, .. } => {
+            infer_expression(expression, types)?;
+        }
+        Statement::Assert { expression, .. } => {
+            let assertion_type = infer_expression(expression, types)?;
+            if assertion_type != Type::Bool {
+                return Err(CompilerError::NonBooleanAssertion(assertion_type));
+            }
+        }
+        Statement::If {
+            expression,
+            statements_a,
+            statements_b,
+            ..
+        } => {
+            let condition_type = infer_expression(expression, types)?;
+            if condition_type != Type::Bool {
+                return Err(CompilerError::NonBooleanCondition(condition_type));
+            }
+            for branch in [statements_a, statements_b] {
+                types.enter_scope();
+                let checked = check_statements(branch, types);
+                types.exit_scope();
+                checked?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn infer_expression(expression: &Expression, types: &SymbolTable<Type>) -> Result<Type> {
+    grow_stack(|| match expression {
+        Expression::Binary {
+            left,
+            operator,
+            right,
+        } => {
+            let left = infer_expression(left, types)?;
+            let right = infer_expression(right, types)?;
+            infer_binary(operator, left, right)
+        }
+        Expression::Value(value) => infer_value(value, types),
+    })
+}
+
+pub fn infer_value(value: &Value, types: &SymbolTable<Type>) -> Result<Type> {
+    match value {
+        Value::Integer(integer) => Ok(Type::from(integer.integer_type)),
+        Value::Field(_) => Ok(Type::Field),
+        Value::Scalar(_) => Ok(Type::Scalar),
+        Value::Group(_) => Ok(Type::Group),
+        Value::Address(_) => Ok(Type::Address),
+        Value::Boolean(_) => Ok(Type::Bool),
+        Value::Identifier(name) => match types.lookup(name) {
+            Some(value_type) => Ok(value_type.clone()),
+            //resolve reports these with more context, but don't rely on it having run
+            None => Err(CompilerError::UndefinedVariable {
+                name: name.to_string(),
+                similar: None,
+            }),
+        },
+        Value::Expression(expression) => infer_expression(expression, types),
+    }
+}
+
+//result type of applying an operator, arithmetic needs integers, fields or scalars and groups can be
+//added and subtracted, ordering needs integers and equality needs matching types
+pub fn infer_binary(operator: &Operator, left: Type, right: Type) -> Result<Type> {
+    let mismatch = || CompilerError::TypeMismatch {
+        operator: operator.clone(),
+        left: left.clone(),
+        right: right.clone(),
+    };
+    match operator {
+        Operator::Add | Operator::Subtract if left == Type::Group && right == Type::Group => {
+            Ok(Type::Group)
+        }
+        Operator::Add | Operator::Subtract | Operator::Multiply | Operator::Divide => {
+            if (left.is_integer() || matches!(left, Type::Field | Type::Scalar)) && left == right {
+                Ok(left.clone())
+            } else {
+                Err(mismatch())
+            }
+        }
+        Operator::GreaterThan | Operator::LessThan => {
+            if left.is_integer() && left == right {
+                Ok(Type::Bool)
+            } else {
+                Err(mismatch())
+            }
+        }
+        Operator::Equal => {
+            if left == right {
+                Ok(Type::Bool)
+            } else {
+                Err(mismatch())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_well_typed_program() {
+        let program = parse("function main(a: u8, flag: bool) {\n    let b = a + 1u8;\n    let c = flag == b > 2u8;\n    if c {\n        let d = b * 2u8;\n    } else {\n        let d = true;\n    }\n}").unwrap();
+        assert_eq!(check(&program), Ok(()));
+    }
+
+    #[test]
+    fn test_arithmetic_on_unknown_boolean() {
+        let program = parse("function main(b: bool) {\n    let a = 1u8 + b;\n}").unwrap();
+        assert_eq!(
+            check(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::TypeMismatch {
+                operator: Operator::Add,
+                left: Type::U8,
+                right: Type::Bool,
+            })
+        );
+    }
+
+    #[test]
+    fn test_field_arithmetic() {
+        let program = parse("function main(a: field) {\n    let b = a * 2field;\n    let c = b == a;\n    let d = b + 1u8;\n}").unwrap();
+        assert_eq!(
+            check(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::TypeMismatch {
+                operator: Operator::Add,
+                left: Type::Field,
+                right: Type::U8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_group_and_address() {
+        let program = parse("function main(g: group, owner: address) {\n    let a = g - 2group;\n    let b = owner == owner;\n    let c = a * 2scalar;\n}").unwrap();
+        assert_eq!(
+            check(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::TypeMismatch {
+                operator: Operator::Multiply,
+                left: Type::Group,
+                right: Type::Scalar,
+            })
+        );
+    }
+
+    #[test]
+    fn test_reassignment_keeps_the_type() {
+        let program =
+            parse("function main(a: u8) {\n    let b = a;\n    b = a + 1u8;\n    b = true;\n}")
+                .unwrap();
+        assert_eq!(
+            check(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::ReassignmentMismatch {
+                name: String::from("b"),
+                expected: Type::U8,
+                found: Type::Bool,
+            })
+        );
+    }
+
+    #[test]
+    fn test_integer_assertion() {
+        let program = parse("function main(a: u8) {\n    assert(a + 1u8);\n}").unwrap();
+        assert_eq!(
+            check(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::NonBooleanAssertion(Type::U8))
+        );
+    }
+
+    #[test]
+    fn test_integer_condition() {
+        let program =
+            parse("function main(a: u8) {\n    if a {\n        let b = 1u8;\n    } else {\n        let b = 2u8;\n    }\n}").unwrap();
+        assert_eq!(
+            check(&program).map_err(CompilerError::without_span),
+            Err(CompilerError::NonBooleanCondition(Type::U8))
+        );
+    }
+}